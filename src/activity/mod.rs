@@ -1,7 +1,7 @@
 use std::sync::Arc;
 use std::time::Duration;
 
-use crate::model::Track;
+use crate::model::{Source, Track};
 use serenity::client::Context;
 use serenity::gateway::ActivityData;
 use tokio::time::sleep;
@@ -27,14 +27,21 @@ impl Manager {
     }
 
     pub(crate) async fn set_current_playing_track(&self, track: Track) -> serenity::Result<()> {
-        self.context.set_activity(Some(ActivityData::streaming(
-            track.title,
-            track.youtube_url,
-        )?));
+        self.context.set_activity(Some(match track.source {
+            Source::YouTube { url } => ActivityData::streaming(track.title, url)?,
+            Source::LocalFile { .. }
+            | Source::HttpStream { .. }
+            | Source::DiscordAttachment { .. } => ActivityData::listening(track.title),
+        }));
 
         Ok(())
     }
 
+    pub(crate) fn set_current_stream_title(&self, stream_title: String) {
+        self.context
+            .set_activity(Some(ActivityData::listening(stream_title)));
+    }
+
     pub(crate) async fn update_idle_activity(&self) {
         let idle_activities = [
             ActivityData::watching(format!("verze {}", crate::VERSION)),
@@ -0,0 +1,115 @@
+use crate::audio;
+use crate::audio::Metadata;
+use crate::model::{Source, Track};
+use amplify_derive::Display;
+use serenity::all::Attachment;
+use std::io::Cursor;
+use symphonia::core::io::{MediaSource, ReadOnlySource};
+use thiserror::Error;
+use tokio::task::JoinError;
+
+/// In bytes.
+pub(crate) const MAX_SIZE: u32 = 50 * 1024 * 1024;
+/// In bytes. The headers and the tags are usually at the start of the file, so that the rest has to
+/// be downloaded only when the playback starts.
+const PROBE_SIZE: usize = 1024 * 1024;
+
+#[derive(Error, Display, Debug)]
+#[display(Debug)]
+#[allow(dead_code)]
+pub(crate) enum Error {
+    TooLarge,
+    /// The format or the codec is not supported.
+    Unsupported,
+    Download(reqwest::Error),
+    Task(JoinError),
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(reqwest_error: reqwest::Error) -> Self {
+        Self::Download(reqwest_error)
+    }
+}
+
+impl From<JoinError> for Error {
+    fn from(join_error: JoinError) -> Self {
+        Self::Task(join_error)
+    }
+}
+
+/// Downloads the start of the attached file to validate it and read its metadata. The rest is
+/// downloaded only if the start does not suffice, e.g. when the headers are at the end.
+pub(crate) async fn resolve(
+    http_client: &reqwest::Client,
+    attachment: &Attachment,
+) -> Result<Track, Error> {
+    if attachment.size > MAX_SIZE {
+        Err(Error::TooLarge)?;
+    }
+
+    let mut response = http_client
+        .get(&attachment.url)
+        .send()
+        .await?
+        .error_for_status()?;
+    let file_extension = attachment
+        .filename
+        .rsplit_once('.')
+        .map(|(_, file_extension)| file_extension.to_owned());
+
+    let mut data = Vec::new();
+    let mut is_complete = false;
+    while data.len() < PROBE_SIZE {
+        match response.chunk().await? {
+            None => {
+                is_complete = true;
+                break;
+            }
+            Some(chunk) => data.extend_from_slice(&chunk),
+        }
+    }
+    let metadata = match probe(data.clone(), is_complete, file_extension.clone()).await? {
+        Some(metadata) => metadata,
+        None if is_complete => Err(Error::Unsupported)?,
+        None => {
+            while let Some(chunk) = response.chunk().await? {
+                data.extend_from_slice(&chunk);
+            }
+            probe(data, true, file_extension)
+                .await?
+                .ok_or(Error::Unsupported)?
+        }
+    };
+
+    let mut track = Track::new(
+        metadata
+            .title
+            .unwrap_or_else(|| attachment.filename.clone()),
+        Source::DiscordAttachment {
+            url: attachment.url.clone(),
+        },
+        None,
+        metadata.duration,
+    );
+    track.artist = metadata.artist;
+    track.album = metadata.album;
+    Ok(track)
+}
+
+/// Returns `None` if the data cannot be decoded. Only the start of the file is not seekable, so
+/// that the duration is not estimated from its length.
+async fn probe(
+    data: Vec<u8>,
+    is_complete: bool,
+    file_extension: Option<String>,
+) -> Result<Option<Metadata>, Error> {
+    Ok(tokio::task::spawn_blocking(move || {
+        let media_source: Box<dyn MediaSource> = match is_complete {
+            true => Box::new(Cursor::new(data)),
+            false => Box::new(ReadOnlySource::new(Cursor::new(data))),
+        };
+        let mut probe_result = audio::probe(media_source, file_extension.as_deref())?;
+        audio::is_decodable(&probe_result).then(|| audio::read_metadata(&mut probe_result))
+    })
+    .await?)
+}
@@ -0,0 +1,123 @@
+use std::time::Duration;
+use symphonia::core::codecs::CODEC_TYPE_NULL;
+use symphonia::core::io::{MediaSource, MediaSourceStream};
+use symphonia::core::meta::{MetadataOptions, MetadataRevision, StandardTagKey, StandardVisualKey};
+use symphonia::core::probe::{Hint, ProbeResult};
+
+/// Read from the tags of an audio file.
+#[derive(Default)]
+pub(crate) struct Metadata {
+    pub(crate) title: Option<String>,
+    pub(crate) artist: Option<String>,
+    pub(crate) album: Option<String>,
+    pub(crate) duration: Option<Duration>,
+}
+
+pub(crate) struct CoverArt {
+    pub(crate) data: Vec<u8>,
+    pub(crate) media_type: String,
+}
+
+impl CoverArt {
+    pub(crate) fn file_extension(&self) -> &'static str {
+        match self.media_type.as_str() {
+            "image/png" => "png",
+            "image/gif" => "gif",
+            "image/webp" => "webp",
+            _ => "jpg",
+        }
+    }
+}
+
+/// Recognizes the format of the audio, `None` if it is not supported. Blocks the thread while
+/// reading.
+pub(crate) fn probe(
+    media_source: Box<dyn MediaSource>,
+    file_extension: Option<&str>,
+) -> Option<ProbeResult> {
+    let mut hint = Hint::new();
+    if let Some(file_extension) = file_extension {
+        hint.with_extension(file_extension);
+    }
+
+    symphonia::default::get_probe()
+        .format(
+            &hint,
+            MediaSourceStream::new(media_source, Default::default()),
+            &Default::default(),
+            &MetadataOptions::default(),
+        )
+        .ok()
+}
+
+/// Whether there is a decoder for the codec of the default track.
+pub(crate) fn is_decodable(probe_result: &ProbeResult) -> bool {
+    probe_result
+        .format
+        .default_track()
+        .map(|track| track.codec_params.codec)
+        .is_some_and(|codec| {
+            codec != CODEC_TYPE_NULL && symphonia::default::get_codecs().get_codec(codec).is_some()
+        })
+}
+
+/// The revisions from both the container and the format, the latter taking precedence.
+fn metadata_revisions(probe_result: &mut ProbeResult) -> Vec<MetadataRevision> {
+    let mut metadata_revisions = Vec::new();
+    if let Some(metadata) = probe_result.metadata.get()
+        && let Some(metadata_revision) = metadata.current()
+    {
+        metadata_revisions.push(metadata_revision.clone());
+    }
+    if let Some(metadata_revision) = probe_result.format.metadata().current() {
+        metadata_revisions.push(metadata_revision.clone());
+    }
+    metadata_revisions
+}
+
+pub(crate) fn read_metadata(probe_result: &mut ProbeResult) -> Metadata {
+    let mut metadata = Metadata {
+        duration: duration(probe_result),
+        ..Default::default()
+    };
+
+    for metadata_revision in metadata_revisions(probe_result) {
+        for tag in metadata_revision.tags() {
+            let value = Some(tag.value.to_string()).filter(|value| !value.trim().is_empty());
+            match tag.std_key {
+                Some(StandardTagKey::TrackTitle) => metadata.title = value.or(metadata.title),
+                Some(StandardTagKey::Artist) => metadata.artist = value.or(metadata.artist),
+                Some(StandardTagKey::Album) => metadata.album = value.or(metadata.album),
+                _ => (),
+            }
+        }
+    }
+
+    metadata
+}
+
+fn duration(probe_result: &ProbeResult) -> Option<Duration> {
+    let codec_parameters = &probe_result.format.default_track()?.codec_params;
+    let time = codec_parameters
+        .time_base?
+        .calc_time(codec_parameters.n_frames?);
+
+    Some(Duration::from_secs(time.seconds) + Duration::from_secs_f64(time.frac))
+}
+
+/// Reads the front cover, or any other picture if there is none.
+pub(crate) fn read_cover_art(probe_result: &mut ProbeResult) -> Option<CoverArt> {
+    let visuals = metadata_revisions(probe_result)
+        .into_iter()
+        .flat_map(|metadata_revision| metadata_revision.visuals().to_vec())
+        .collect::<Vec<_>>();
+
+    visuals
+        .iter()
+        .find(|visual| visual.usage == Some(StandardVisualKey::FrontCover))
+        .or(visuals.first())
+        .map(|visual| CoverArt {
+            data: visual.data.to_vec(),
+            media_type: visual.media_type.clone(),
+        })
+}
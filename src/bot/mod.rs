@@ -1,17 +1,21 @@
 use crate::command::{
-    Command, Error, FromInteractionError, UserCausedError, register_global_commands,
+    Command, Error, Executed, FromInteractionError, ImportState, SESSION_RESTORE_BUTTON_ID,
+    UserCausedError, import_components, playback_control_components, queue_view_components,
+    register_global_commands, skip_vote_button_id,
 };
-use crate::env::voice;
-use crate::{activity, command, player};
+use crate::env::{SESSION_RESTORE_MODE, voice};
+use crate::{activity, command, embed, player, session, settings};
 use amplify_derive::Display;
 use log::{error, info};
 use serenity::all::{
-    Context, CreateEmbed, CreateMessage, EditInteractionResponse, EventHandler, GuildId,
-    Interaction, Ready,
+    ButtonStyle, CommandInteraction, ComponentInteraction, Context, CreateActionRow, CreateButton,
+    CreateEmbed, CreateInteractionResponseFollowup, CreateMessage, EditInteractionResponse,
+    EditMessage, EventHandler, GuildId, Interaction, Message, Ready, VoiceState,
 };
 use serenity::async_trait;
 use songbird::events::context_data::VoiceTick;
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 use tokio::sync::RwLock;
 use unwrap_or_log::LogError;
@@ -20,10 +24,20 @@ use unwrap_or_log::LogError;
 #[display(Debug)]
 #[allow(dead_code)]
 pub(crate) enum CreationError {
+    SettingsManager(settings::Error),
+    SessionManager(session::Error),
     CommandExecutor(command::ExecutorCreationError),
     VoiceCommandHandler(command::voice::TranscriptorCreationError),
 }
 
+/// How often at most the message describing an enqueued query is updated with the progress of
+/// enqueueing the rest of its tracks.
+const IMPORT_PROGRESS_UPDATE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The embed and the components replying to a successfully executed command, along with the query
+/// being enqueued in the background, or the embed describing the error.
+type Reply = Result<(CreateEmbed, Vec<CreateActionRow>, Option<command::Enqueued>), CreateEmbed>;
+
 pub(crate) struct Bot {
     context: Context,
     command_executor: Arc<command::Executor<Arc<Self>>>,
@@ -34,16 +48,30 @@ pub(crate) struct Bot {
     voice_command_transcriptor: Option<Arc<command::voice::Transcriptor<Arc<Self>>>>,
     #[allow(dead_code)]
     activity_manager: Arc<activity::Manager>,
+    session_manager: Arc<session::Manager>,
 }
 
 impl Bot {
     pub(crate) async fn new(context: Context) -> Result<Arc<Self>, CreationError> {
         let activity_manager = activity::Manager::new(context.clone());
+        let settings_manager = Arc::new(
+            settings::Manager::new()
+                .await
+                .map_err(CreationError::SettingsManager)?,
+        );
+        let session_manager = session::Manager::new()
+            .await
+            .map_err(CreationError::SessionManager)?;
 
         let command_executor = Arc::new(
-            command::Executor::new(None, activity_manager.clone())
-                .await
-                .map_err(CreationError::CommandExecutor)?,
+            command::Executor::new(
+                None,
+                activity_manager.clone(),
+                settings_manager,
+                session_manager.clone(),
+            )
+            .await
+            .map_err(CreationError::CommandExecutor)?,
         );
         let voice_command_transcriptor = if voice::COMMANDS_ENABLED == "true" {
             Some(
@@ -60,6 +88,7 @@ impl Bot {
             command_executor,
             voice_command_transcriptor,
             activity_manager,
+            session_manager,
         });
 
         new.command_executor
@@ -75,34 +104,201 @@ impl Bot {
     }
 
     async fn on_interaction_create(&self, context: Context, interaction: Interaction) {
-        let command_interaction = match interaction {
-            Interaction::Command(command_interaction) => command_interaction,
-            _ => return,
-        };
+        match interaction {
+            Interaction::Command(command_interaction) => {
+                self.on_command_interaction(context, command_interaction)
+                    .await
+            }
+            Interaction::Component(component_interaction) => {
+                self.on_component_interaction(context, component_interaction)
+                    .await
+            }
+            _ => (),
+        }
+    }
 
+    async fn on_command_interaction(
+        &self,
+        context: Context,
+        command_interaction: CommandInteraction,
+    ) {
         let cache_http = context.http.clone();
 
         _ = command_interaction.defer(&context).await.log_error();
-        let embed = match Command::try_from_interaction(&command_interaction, &context).await {
-            Err(error) => match error {
+        let command = Command::try_from_interaction(&command_interaction, &context).await;
+        let (embed, components, enqueued) = self
+            .try_execute_command(context, command)
+            .await
+            .unwrap_or_else(|embed| (embed, Vec::new(), None));
+
+        let message = command_interaction
+            .edit_response(
+                cache_http,
+                EditInteractionResponse::new()
+                    .embed(embed)
+                    .components(components),
+            )
+            .await
+            .log_error();
+        if let (Ok(message), Some(enqueued)) = (message, enqueued) {
+            self.follow_import(&message, enqueued);
+        }
+    }
+
+    async fn on_component_interaction(
+        &self,
+        context: Context,
+        component_interaction: ComponentInteraction,
+    ) {
+        let cache_http = context.http.clone();
+
+        _ = component_interaction.defer(&context).await.log_error();
+        let command =
+            Command::try_from_component_interaction(&component_interaction, &context).await;
+        if let Ok(command) = &command
+            && command.is_playback_control()
+        {
+            self.on_playback_control_interaction(context, &component_interaction, command)
+                .await;
+            return;
+        }
+        // The message is updated by the import itself once cancelled.
+        if let Ok(command) = &command
+            && command.is_import_cancel()
+        {
+            if let Err(embed) = self.execute_command(context, command).await {
+                _ = component_interaction
+                    .create_followup(
+                        cache_http,
+                        CreateInteractionResponseFollowup::new()
+                            .embed(embed)
+                            .ephemeral(true),
+                    )
+                    .await
+                    .log_error();
+            }
+            return;
+        }
+
+        match self.try_execute_command(context, command).await {
+            Ok((embed, components, enqueued)) => {
+                let message = component_interaction
+                    .edit_response(
+                        cache_http,
+                        EditInteractionResponse::new()
+                            .embed(embed)
+                            .components(components),
+                    )
+                    .await
+                    .log_error();
+                if let (Ok(message), Some(enqueued)) = (message, enqueued) {
+                    self.follow_import(&message, enqueued);
+                }
+            }
+            // The message with the component is shared, so the error is shown only to the user.
+            Err(embed) => {
+                _ = component_interaction
+                    .create_followup(
+                        cache_http,
+                        CreateInteractionResponseFollowup::new()
+                            .embed(embed)
+                            .ephemeral(true),
+                    )
+                    .await
+                    .log_error();
+            }
+        }
+    }
+
+    /// Updates the controls of the message in place, other outcomes, e.g. a vote to skip, are
+    /// replied to separately. The live message announcing the current track is left to the player.
+    async fn on_playback_control_interaction(
+        &self,
+        context: Context,
+        component_interaction: &ComponentInteraction,
+        command: &Command,
+    ) {
+        let cache_http = context.http.clone();
+
+        let executed_command = self.command_executor.execute(context, command).await;
+        let is_now_playing_message = match component_interaction.guild_id {
+            None => false,
+            Some(guild_id) => {
+                self.command_executor
+                    .player_now_playing_message_id(&guild_id)
+                    .await
+                    == Some(component_interaction.message.id)
+            }
+        };
+
+        let components = match executed_command {
+            Ok(
+                Executed::Pause
+                | Executed::Resume
+                | Executed::Repeat(_)
+                | Executed::Next
+                | Executed::Previous
+                | Executed::Stop,
+            ) if is_now_playing_message => return,
+            Ok(Executed::Pause | Executed::Resume | Executed::Repeat(_)) => {
+                let playback_state = match component_interaction.guild_id {
+                    None => None,
+                    Some(guild_id) => self.command_executor.player_playback_state(&guild_id).await,
+                };
+                playback_control_components(playback_state.unwrap_or_default())
+            }
+            // The message no longer describes the current track.
+            Ok(Executed::Next | Executed::Previous | Executed::Stop) => Vec::new(),
+            executed_command => {
+                let followup = match Self::reply(executed_command) {
+                    Ok((embed, components, _)) => CreateInteractionResponseFollowup::new()
+                        .embed(embed)
+                        .components(components),
+                    Err(embed) => CreateInteractionResponseFollowup::new()
+                        .embed(embed)
+                        .ephemeral(true),
+                };
+                _ = component_interaction
+                    .create_followup(cache_http, followup)
+                    .await
+                    .log_error();
+                return;
+            }
+        };
+
+        _ = component_interaction
+            .edit_response(
+                cache_http,
+                EditInteractionResponse::new().components(components),
+            )
+            .await
+            .log_error();
+    }
+
+    async fn try_execute_command(
+        &self,
+        context: Context,
+        command: Result<Command, FromInteractionError>,
+    ) -> Reply {
+        match command {
+            Err(error) => Err(match error {
                 FromInteractionError::UserCaused(error) => error.into(),
                 FromInteractionError::Internal(error) => {
                     error!("{error}");
                     error.into()
                 }
-            },
+            }),
             Ok(command) => self.execute_command(context, &command).await,
-        };
+        }
+    }
 
-        _ = command_interaction
-            .edit_response(cache_http, EditInteractionResponse::new().embed(embed))
-            .await
-            .log_error();
+    async fn execute_command(&self, context: Context, command: &Command) -> Reply {
+        Self::reply(self.command_executor.execute(context, command).await)
     }
 
-    async fn execute_command(&self, context: Context, command: &Command) -> CreateEmbed {
-        match self.command_executor.execute(context, command).await {
-            Err(error) => match error {
+    fn reply(executed_command: Result<Executed, Error>) -> Reply {
+        match executed_command {
+            Err(error) => Err(match error {
                 Error::UserCaused(error) => {
                     if let UserCausedError::CouldNotJoin(error) = &error {
                         error!("{error}");
@@ -113,8 +309,115 @@ impl Bot {
                     error!("{error}");
                     error.into()
                 }
-            },
-            Ok(executed_command) => executed_command.into(),
+            }),
+            Ok(executed_command) => {
+                let enqueued = match &executed_command {
+                    Executed::Play(Some(enqueued)) => Some(enqueued.clone()),
+                    _ => None,
+                };
+                let components = match &executed_command {
+                    Executed::SkipVote(skip_vote) => {
+                        vec![CreateActionRow::Buttons(vec![
+                            CreateButton::new(skip_vote_button_id(skip_vote.round))
+                                .label("Hlasovat pro přeskočení")
+                                .style(ButtonStyle::Primary),
+                        ])]
+                    }
+                    Executed::QueueView {
+                        page, page_count, ..
+                    } => queue_view_components(*page, *page_count),
+                    Executed::Play(Some(enqueued))
+                        if enqueued.progress.borrow().state == ImportState::InProgress =>
+                    {
+                        import_components(enqueued.import_id)
+                    }
+                    _ => Vec::new(),
+                };
+                Ok((executed_command.into(), components, enqueued))
+            }
+        }
+    }
+
+    /// Keeps the message describing the enqueued query up to date until the rest of its tracks
+    /// are enqueued.
+    fn follow_import(&self, message: &Message, mut enqueued: command::Enqueued) {
+        let http = self.context.http.clone();
+        let channel_id = message.channel_id;
+        let message_id = message.id;
+        tokio::spawn(async move {
+            // Fails once the import has ended and its final progress has been seen.
+            while enqueued.progress.changed().await.is_ok() {
+                tokio::time::sleep(IMPORT_PROGRESS_UPDATE_INTERVAL).await;
+                let is_in_progress =
+                    enqueued.progress.borrow_and_update().state == ImportState::InProgress;
+
+                let components = match is_in_progress {
+                    true => import_components(enqueued.import_id),
+                    false => Vec::new(),
+                };
+                let edited = channel_id
+                    .edit_message(
+                        http.clone(),
+                        message_id,
+                        EditMessage::new()
+                            .embed(embed::enqueued(&enqueued))
+                            .components(components),
+                    )
+                    .await
+                    .log_error();
+                if !is_in_progress || edited.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Offers to resume or directly resumes the sessions interrupted by the last restart, depending
+    /// on the configured mode.
+    async fn restore_sessions(&self) {
+        if SESSION_RESTORE_MODE != "automatic" && SESSION_RESTORE_MODE != "button" {
+            return;
+        }
+
+        let sessions = match self.session_manager.load_all().await {
+            Err(error) => {
+                error!("{error}");
+                return;
+            }
+            Ok(sessions) => sessions,
+        };
+
+        for (guild_id, session) in sessions {
+            if session.queue.tracks.is_empty() {
+                continue;
+            }
+
+            let message = if SESSION_RESTORE_MODE == "automatic" {
+                let command = Command::session_restore(
+                    guild_id,
+                    session.voice_channel_id,
+                    session.text_channel_id,
+                );
+                let (embed, components, _) = self
+                    .execute_command(self.context.clone(), &command)
+                    .await
+                    .unwrap_or_else(|embed| (embed, Vec::new(), None));
+                CreateMessage::new().embed(embed).components(components)
+            } else {
+                CreateMessage::new()
+                    .embed(embed::session_restore_offer(&session))
+                    .components(vec![CreateActionRow::Buttons(vec![
+                        CreateButton::new(SESSION_RESTORE_BUTTON_ID)
+                            .label("Obnovit relaci")
+                            .style(ButtonStyle::Primary),
+                    ])])
+            };
+
+            _ = session
+                .text_channel_id
+                .send_message(self.context.http.clone(), message)
+                .await
+                .log_error();
         }
     }
 }
@@ -132,7 +435,12 @@ impl player::VoiceTickCallback for Arc<Bot> {
 
 #[async_trait]
 impl command::voice::VoiceTranscribedCallback for Arc<Bot> {
-    async fn on_voice_transcribed(&self, guild_id: GuildId, text: String) {
+    async fn on_voice_transcribed(
+        &self,
+        guild_id: GuildId,
+        speaker_ssrc: Option<u32>,
+        text: String,
+    ) {
         let text_channel_id = match self
             .command_executor
             .player_text_channel_id(&guild_id)
@@ -142,16 +450,34 @@ impl command::voice::VoiceTranscribedCallback for Arc<Bot> {
             Some(text_channel_id) => text_channel_id,
         };
 
-        let command = match Command::try_from_text(text, guild_id) {
+        let user_id = match speaker_ssrc {
+            None => None,
+            Some(speaker_ssrc) => {
+                self.command_executor
+                    .player_user_id_by_ssrc(&guild_id, speaker_ssrc)
+                    .await
+            }
+        };
+
+        let command = match Command::try_from_text(text, guild_id, user_id) {
             Err(_) => return,
             Ok(command) => command,
         };
 
-        let embed = self.execute_command(self.context.clone(), &command).await;
-        let _ = text_channel_id
-            .send_message(self.context.http.clone(), CreateMessage::new().embed(embed))
+        let (embed, components, enqueued) = self
+            .execute_command(self.context.clone(), &command)
+            .await
+            .unwrap_or_else(|embed| (embed, Vec::new(), None));
+        let message = text_channel_id
+            .send_message(
+                self.context.http.clone(),
+                CreateMessage::new().embed(embed).components(components),
+            )
             .await
             .log_error();
+        if let (Ok(message), Some(enqueued)) = (message, enqueued) {
+            self.follow_import(&message, enqueued);
+        }
     }
 }
 
@@ -169,7 +495,15 @@ impl Handler {
 
 #[async_trait]
 impl EventHandler for Handler {
+    /// The event repeats after every reconnection to the gateway, yet the bot is set up and the
+    /// sessions are restored only the first time.
     async fn ready(&self, context: Context, _: Ready) {
+        let mut bot = self.bot.write().await;
+        if bot.is_some() {
+            info!("The bot has reconnected.");
+            return;
+        }
+
         register_global_commands(&context)
             .await
             .log_error()
@@ -177,7 +511,11 @@ impl EventHandler for Handler {
 
         info!("The bot is ready.");
 
-        *self.bot.write().await = Some(Bot::new(context).await.log_error().unwrap());
+        let new_bot = Bot::new(context).await.log_error().unwrap();
+        *bot = Some(new_bot.clone());
+        drop(bot);
+
+        new_bot.restore_sessions().await;
     }
 
     async fn interaction_create(&self, context: Context, interaction: Interaction) {
@@ -188,4 +526,15 @@ impl EventHandler for Handler {
         };
         bot.on_interaction_create(context, interaction).await;
     }
+
+    async fn voice_state_update(&self, context: Context, _: Option<VoiceState>, new: VoiceState) {
+        let bot = self.bot.read().await;
+        let (bot, guild_id) = match (bot.as_ref(), new.guild_id) {
+            (Some(bot), Some(guild_id)) => (bot, guild_id),
+            _ => return,
+        };
+        bot.command_executor
+            .on_voice_state_update(&context, guild_id)
+            .await;
+    }
 }
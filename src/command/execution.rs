@@ -1,27 +1,48 @@
-use crate::command::{Action, Command};
-use crate::embed::EmbedIcon;
+use crate::command::{
+    Action, ActionKind, Command, Enqueued, ImportProgress, ImportSource, ImportState, ImportTask,
+    SettingsAction, playback_control_components,
+};
+use crate::env::library::DIRECTORY_PATH as LIBRARY_DIRECTORY_PATH;
 use crate::env::{SPOTIFY_API_CLIENT_ID, SPOTIFY_API_CLIENT_SECRET};
-use crate::player::{Player, Track};
+use crate::library::Library;
+use crate::model::Source;
+use crate::player::{EnqueuePosition, Player, Track};
 use crate::query::Fetcher;
-use crate::{activity, embed, player, query, youtube};
+use crate::settings::{Limits, Requirement};
+use crate::{activity, attachment, embed, library, player, query, session, settings, youtube};
 use amplify_derive::Display;
 use log::error;
 use rspotify::ClientCredsSpotify;
-use serenity::all::{ChannelId, Context, CreateMessage, GuildId};
+use serenity::all::{
+    Attachment, ChannelId, Context, CreateAttachment, CreateMessage, EditMessage, GetMessages,
+    GuildId, MessageId, RoleId, UserId,
+};
 use serenity::async_trait;
-use songbird::error::JoinError;
+use songbird::error::{JoinError, PlayError};
 use std::collections::HashMap;
+use std::ops::RangeInclusive;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 use thiserror::Error;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, oneshot, watch};
+use tokio_util::sync::CancellationToken;
 use unwrap_or_log::LogError;
 
+/// How many newer messages may follow the live message announcing the current track before it is
+/// sent again at the bottom of the channel.
+const NOW_PLAYING_MESSAGE_MAX_BURIAL_DEPTH: u8 = 10;
+pub(crate) const QUEUE_VIEW_PAGE_LENGTH: usize = 15;
+
 #[derive(Error, Display, Debug)]
 #[display(Debug)]
 #[allow(dead_code)]
 pub(crate) enum ExecutorCreationError {
     SpotifyClientTokenRetrieval(rspotify::ClientError),
-    YouTubePlaylistFetcherCreation(rustypipe::error::Error),
+    RustyPipeClientCreation(rustypipe::error::Error),
+    LibraryIndexing(library::Error),
+    SpotifyCacheLoading(query::spotify::cache::Error),
+    HttpClientCreation(reqwest::Error),
 }
 
 #[derive(Error, Display, Debug)]
@@ -50,8 +71,35 @@ pub(crate) enum UserCausedError {
     CouldNotJoin(JoinError),
     NotPlaying,
     QueueMove(player::QueueMoveIndexExceedsQueueLengthError),
+    QueueRemove(player::QueueRemoveIndexExceedsQueueLengthError),
+    QueueReorder(player::QueueReorderIndexExceedsQueueLengthError),
     Next(player::NextNoTrackError),
     Previous(player::PreviousNoTrackError),
+    SeekUnsupported,
+    AlreadyPlaying,
+    NoSessionToRestore,
+    AttachmentTooLarge(String),
+    AttachmentUnsupported(String),
+    UnknownUser,
+    SkipVoteExpired,
+    InsufficientPermissions(Requirement),
+    SpotifyMatchNotCached,
+    /// The action affects all the guilds, so only the owner of the bot may perform it.
+    NotBotOwner,
+    LimitReached(Limit),
+    /// The fair queue would override the requested order.
+    QueueOrderedFairly,
+    ImportCancelled,
+    ImportNotRunning,
+}
+
+/// Prevents some of the fetched tracks from being enqueued.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Limit {
+    QueueLength(usize),
+    UserTrackCount(usize),
+    TrackDuration(Duration),
+    PlaylistImportSize(usize),
 }
 
 #[derive(Error, Display, Debug)]
@@ -60,22 +108,67 @@ pub(crate) enum UserCausedError {
 pub(crate) enum InternalError {
     PlayerCreation(player::CreationError),
     Play(anyhow::Error),
+    Attachment(attachment::Error),
     Pause(songbird::error::ControlError),
     Resume(songbird::error::ControlError),
+    Seek(songbird::error::ControlError),
+    Volume(songbird::error::ControlError),
+    SessionLoad(session::Error),
+    SettingsSave(settings::Error),
+    ApplicationInfoRetrieval(serenity::Error),
+    /// The import task has ended without reporting its outcome, i.e. it has panicked.
+    ImportPanicked,
 }
 
-pub(crate) enum Executed<'a> {
-    Play(Option<query::Fetched<'a>>),
-    QueueView(player::Queue),
-    QueueMove { index: usize },
+pub(crate) enum Executed {
+    Play(Option<Enqueued>),
+    ImportCancel,
+    QueueView {
+        status: player::QueueStatus,
+        page: usize,
+        page_count: usize,
+    },
+    QueueMove {
+        index: usize,
+    },
+    QueueRemove {
+        indices: RangeInclusive<usize>,
+    },
+    QueueRemoveOwn {
+        track_count: usize,
+    },
+    QueueReorder {
+        from_index: usize,
+        to_index: usize,
+    },
     QueueRepeat(bool),
+    QueueFair(bool),
     QueueShuffle,
     Next,
+    SkipVote(player::SkipVote),
     Previous,
     Pause,
     Resume,
+    Seek(Duration),
+    Volume(u16),
     Repeat(bool),
+    Autoplay(bool),
     Stop,
+    SessionRestore {
+        track_count: usize,
+    },
+    VoteSkipSettings {
+        enabled: bool,
+        percentage: u8,
+    },
+    DjRole(Option<RoleId>),
+    LiveNowPlaying(bool),
+    Permission {
+        action_kind: ActionKind,
+        requirement: Requirement,
+    },
+    Limits(settings::Limits),
+    SpotifyMatchForget,
 }
 
 impl From<player::CreationError> for InternalError {
@@ -86,6 +179,13 @@ impl From<player::CreationError> for InternalError {
 
 type PlayerMap<S, V> = HashMap<GuildId, Arc<Mutex<Player<S, V>>>>;
 
+/// The enqueueing of a query running in the background.
+struct Import {
+    guild_id: GuildId,
+    requester: Option<UserId>,
+    cancellation_token: CancellationToken,
+}
+
 pub(crate) struct Executor<V: player::VoiceTickCallback> {
     http_client: reqwest::Client,
     /* It is not needed to store these two in this struct, but this way they are noted as a part of
@@ -94,19 +194,31 @@ pub(crate) struct Executor<V: player::VoiceTickCallback> {
     youtube_searcher: Arc<youtube::Searcher>,
     #[allow(dead_code)]
     spotify_client: Arc<ClientCredsSpotify>,
-    query_fetchers: [Box<dyn Fetcher + Send + Sync>; 4],
+    spotify_cache: Arc<query::spotify::cache::Cache>,
+    query_fetchers: [Box<dyn Fetcher + Send + Sync>; 7],
+    youtube_recommender: Arc<youtube::Recommender>,
     players: Mutex<PlayerMap<Arc<Self>, V>>,
+    imports: Mutex<HashMap<u64, Import>>,
+    next_import_id: AtomicU64,
     voice_tick_callback: Mutex<Option<V>>,
     activity_manager: Arc<activity::Manager>,
+    settings_manager: Arc<settings::Manager>,
+    session_manager: Arc<session::Manager>,
 }
 
 impl<V: player::VoiceTickCallback> Executor<V> {
     pub(crate) async fn new(
         on_voice_tick_callback: Option<V>,
         activity_manager: Arc<activity::Manager>,
+        settings_manager: Arc<settings::Manager>,
+        session_manager: Arc<session::Manager>,
     ) -> Result<Self, ExecutorCreationError> {
         let http_client = reqwest::Client::new();
-        let youtube_searcher = Arc::new(youtube::Searcher::new(http_client.clone()));
+        // The links given by the users are requested without following the redirects
+        // automatically, so that none of them leads to a private address.
+        let probing_http_client = query::http::create_probing_client()
+            .map_err(ExecutorCreationError::HttpClientCreation)?;
+        let youtube_searcher = Arc::new(youtube::Searcher::new());
         let spotify_client = Arc::new(ClientCredsSpotify::new(rspotify::Credentials::new(
             SPOTIFY_API_CLIENT_ID,
             SPOTIFY_API_CLIENT_SECRET,
@@ -115,37 +227,86 @@ impl<V: player::VoiceTickCallback> Executor<V> {
             .request_token()
             .await
             .map_err(ExecutorCreationError::SpotifyClientTokenRetrieval)?;
+        let rusty_pipe_client = youtube::create_rusty_pipe_client()
+            .map_err(ExecutorCreationError::RustyPipeClientCreation)?;
+        let library = Arc::new(
+            Library::index(LIBRARY_DIRECTORY_PATH)
+                .await
+                .map_err(ExecutorCreationError::LibraryIndexing)?,
+        );
+        let spotify_cache = query::spotify::cache::Cache::new()
+            .await
+            .map_err(ExecutorCreationError::SpotifyCacheLoading)?;
 
         Ok(Self {
             http_client,
             youtube_searcher: youtube_searcher.clone(),
             spotify_client: spotify_client.clone(),
+            spotify_cache: spotify_cache.clone(),
             query_fetchers: [
                 Box::new(query::spotify::playlist::Fetcher::new(
                     spotify_client.clone(),
                     youtube_searcher.clone(),
+                    spotify_cache.clone(),
                 )),
                 Box::new(query::spotify::track::Fetcher::new(
                     spotify_client,
                     youtube_searcher.clone(),
+                    spotify_cache,
                 )),
-                Box::new(
-                    query::youtube::playlist::Fetcher::new()
-                        .map_err(ExecutorCreationError::YouTubePlaylistFetcherCreation)?,
-                ),
+                Box::new(query::youtube::playlist::Fetcher::new(
+                    rusty_pipe_client.clone(),
+                )),
+                // Before the links to audio, so that a feed is downloaded only once, while the
+                // audio is probed by its headers only.
+                Box::new(query::podcast::Fetcher::new(probing_http_client.clone())),
+                Box::new(query::http::Fetcher::new(probing_http_client)),
+                Box::new(query::library::Fetcher::new(library)),
                 Box::new(query::youtube::search::Fetcher::new(youtube_searcher)),
             ],
+            youtube_recommender: Arc::new(youtube::Recommender::new(rusty_pipe_client)),
             players: Mutex::new(HashMap::new()),
+            imports: Mutex::new(HashMap::new()),
+            next_import_id: AtomicU64::new(0),
             activity_manager,
+            settings_manager,
+            session_manager,
             voice_tick_callback: Mutex::new(on_voice_tick_callback),
         })
     }
 
-    pub(crate) async fn execute<'a>(
-        self: &'a Arc<Self>,
+    pub(crate) async fn execute(
+        self: &Arc<Self>,
         context: Context,
-        command: &'a Command,
-    ) -> Result<Executed<'a>, Error> {
+        command: &Command,
+    ) -> Result<Executed, Error> {
+        if let Action::Settings(settings_action) = &command.action {
+            return self
+                .execute_settings_action(command.guild_id, settings_action)
+                .await;
+        }
+        if let Action::SpotifyMatchForget { spotify_track_id } = &command.action {
+            self.check_bot_owner(&context, command).await?;
+            return match self.spotify_cache.remove(spotify_track_id).await {
+                true => Ok(Executed::SpotifyMatchForget),
+                false => Err(UserCausedError::SpotifyMatchNotCached)?,
+            };
+        }
+        self.check_permissions(&context, command).await?;
+        if let Action::ImportCancel { import_id } = command.action {
+            return self.cancel_import(&context, command, import_id).await;
+        }
+
+        let mut session_to_restore = match command.action {
+            Action::SessionRestore { .. } => Some(
+                self.session_manager
+                    .load(command.guild_id)
+                    .await
+                    .map_err(InternalError::SessionLoad)?
+                    .ok_or(UserCausedError::NoSessionToRestore)?,
+            ),
+            _ => None,
+        };
         let player = {
             let player = self
                 .players
@@ -161,6 +322,9 @@ impl<V: player::VoiceTickCallback> Executor<V> {
 
             match (player, player_is_stopped) {
                 (Some(player), false) => {
+                    if let Action::SessionRestore { .. } = command.action {
+                        Err(UserCausedError::AlreadyPlaying)?;
+                    }
                     match player.lock().await.voice_channel_id().await.log_error() {
                         Err(error) => {
                             error!("{error}");
@@ -182,6 +346,15 @@ impl<V: player::VoiceTickCallback> Executor<V> {
                         text_channel_id,
                         voice_channel_id,
                         ..
+                    }
+                    | Action::PlayAttachments {
+                        text_channel_id,
+                        voice_channel_id,
+                        ..
+                    }
+                    | Action::SessionRestore {
+                        text_channel_id,
+                        voice_channel_id,
                     } => match self
                         .clone()
                         .create_player(command.guild_id, voice_channel_id, text_channel_id, context)
@@ -200,17 +373,97 @@ impl<V: player::VoiceTickCallback> Executor<V> {
             }
         };
 
+        // Downloaded only once the user is known to be able to play them.
+        let mut attachment_tracks = match &command.action {
+            Action::PlayAttachments { attachments, .. } => {
+                Some(self.resolve_attachments(attachments).await?)
+            }
+            _ => None,
+        };
+
         if let Some(text_channel_id) = command.text_channel_id {
-            player.lock().await.set_text_channel_id(text_channel_id);
+            player
+                .lock()
+                .await
+                .set_text_channel_id(text_channel_id)
+                .await;
         }
 
+        let guild_settings = self.settings_manager.guild(command.guild_id).await;
         Ok(match &command.action {
-            Action::Play { query, .. } | Action::VoicePlay { query } => Executed::Play(
-                self.fetch_and_enqueue_query(&player, query)
-                    .await
-                    .map_err(InternalError::Play)?,
+            Action::Play {
+                query,
+                position,
+                track_limit,
+                ..
+            } => Executed::Play(
+                self.start_import(
+                    &player,
+                    command,
+                    ImportSource::Query(query.clone()),
+                    *position,
+                    *track_limit,
+                    guild_settings.limits,
+                )
+                .await?,
+            ),
+            Action::VoicePlay { query } => Executed::Play(
+                self.start_import(
+                    &player,
+                    command,
+                    ImportSource::Query(query.clone()),
+                    EnqueuePosition::Back,
+                    None,
+                    guild_settings.limits,
+                )
+                .await?,
             ),
-            Action::QueueView => Executed::QueueView(player.lock().await.queue().clone()),
+            Action::PlayAttachments { position, .. } => {
+                let tracks = attachment_tracks.take().unwrap_or_default();
+                let track_count = tracks.len();
+                let fetched_query = query::Fetched::new(
+                    match tracks.as_slice() {
+                        [track] => track.title.clone(),
+                        tracks => format!("Přiložené soubory ({})", tracks.len()),
+                    },
+                    None,
+                    None,
+                    Box::new(query::FetchedTrackList::new(tracks)),
+                )
+                .with_track_count(track_count);
+                Executed::Play(
+                    self.start_import(
+                        &player,
+                        command,
+                        ImportSource::Fetched(fetched_query),
+                        *position,
+                        None,
+                        guild_settings.limits,
+                    )
+                    .await?,
+                )
+            }
+            Action::QueueView { page } => {
+                let status = player.lock().await.queue_status().await;
+                let page_count = status
+                    .queue
+                    .tracks
+                    .len()
+                    .div_ceil(QUEUE_VIEW_PAGE_LENGTH)
+                    .max(1);
+                // The queue could have shrunk since the page was shown.
+                let page = page
+                    .unwrap_or_else(|| {
+                        status.queue.current_playing_track_index.unwrap_or(0)
+                            / QUEUE_VIEW_PAGE_LENGTH
+                    })
+                    .min(page_count - 1);
+                Executed::QueueView {
+                    status,
+                    page,
+                    page_count,
+                }
+            }
             Action::QueueMove { index } => player
                 .lock()
                 .await
@@ -218,21 +471,86 @@ impl<V: player::VoiceTickCallback> Executor<V> {
                 .await
                 .map(|_| Executed::QueueMove { index: *index })
                 .map_err(UserCausedError::QueueMove)?,
+            Action::QueueRemove { indices } => player
+                .lock()
+                .await
+                .queue_remove(indices.clone())
+                .await
+                .map(|_| Executed::QueueRemove {
+                    indices: indices.clone(),
+                })
+                .map_err(UserCausedError::QueueRemove)?,
+            Action::QueueRemoveOwn => {
+                let user_id = command.user_id.ok_or(UserCausedError::UnknownUser)?;
+                Executed::QueueRemoveOwn {
+                    track_count: player.lock().await.queue_remove_requested_by(user_id).await,
+                }
+            }
+            Action::QueueReorder {
+                from_index,
+                to_index,
+            } => {
+                let mut player = player.lock().await;
+                if player.is_queue_fair() {
+                    Err(UserCausedError::QueueOrderedFairly)?;
+                }
+                player
+                    .queue_reorder(*from_index, *to_index)
+                    .await
+                    .map(|_| Executed::QueueReorder {
+                        from_index: *from_index,
+                        to_index: *to_index,
+                    })
+                    .map_err(UserCausedError::QueueReorder)?
+            }
             Action::QueueRepeat(repeat) => {
                 player.lock().await.queue_repeat(*repeat).await;
                 Executed::QueueRepeat(*repeat)
             }
+            Action::QueueFair(fair) => {
+                player.lock().await.set_fair_queue(*fair).await;
+                _ = self
+                    .settings_manager
+                    .update_guild(command.guild_id, |guild_settings| {
+                        guild_settings.fair_queue = *fair
+                    })
+                    .await
+                    .log_error();
+                Executed::QueueFair(*fair)
+            }
             Action::QueueShuffle => {
-                player.lock().await.queue_shuffle().await;
+                let mut player = player.lock().await;
+                if player.is_queue_fair() {
+                    Err(UserCausedError::QueueOrderedFairly)?;
+                }
+                player.queue_shuffle().await;
                 Executed::QueueShuffle
             }
-            Action::Next => player
-                .lock()
-                .await
-                .next()
-                .await
-                .map(|_| Executed::Next)
-                .map_err(UserCausedError::Next)?,
+            Action::Next => {
+                let mut player = player.lock().await;
+                match self.vote_skip(&context, command, &mut player, None).await? {
+                    Some(skip_vote) => skip_vote,
+                    None => player
+                        .next()
+                        .await
+                        .map(|_| Executed::Next)
+                        .map_err(UserCausedError::Next)?,
+                }
+            }
+            Action::SkipVote { round } => {
+                let mut player = player.lock().await;
+                match self
+                    .vote_skip(&context, command, &mut player, Some(*round))
+                    .await?
+                {
+                    Some(skip_vote) => skip_vote,
+                    None => player
+                        .next()
+                        .await
+                        .map(|_| Executed::Next)
+                        .map_err(UserCausedError::Next)?,
+                }
+            }
             Action::Previous => player
                 .lock()
                 .await
@@ -254,14 +572,231 @@ impl<V: player::VoiceTickCallback> Executor<V> {
                 .await
                 .map(|_| Executed::Resume)
                 .map_err(InternalError::Resume)?,
+            Action::Seek(position) => match player.lock().await.seek(*position).await {
+                Ok(position) => Executed::Seek(position),
+                Err(player::SeekError::NotPlaying) => Err(UserCausedError::NotPlaying)?,
+                Err(player::SeekError::Unsupported) => Err(UserCausedError::SeekUnsupported)?,
+                Err(player::SeekError::Control(error)) => Err(InternalError::Seek(error))?,
+            },
+            Action::Volume(volume_change) => {
+                let volume = player
+                    .lock()
+                    .await
+                    .change_volume(*volume_change)
+                    .await
+                    .map_err(InternalError::Volume)?;
+                _ = self
+                    .settings_manager
+                    .update_guild(command.guild_id, |guild_settings| {
+                        guild_settings.volume = volume
+                    })
+                    .await
+                    .log_error();
+                Executed::Volume(volume)
+            }
             Action::Repeat(repeat) => {
                 player.lock().await.repeat(*repeat).await;
                 Executed::Repeat(*repeat)
             }
+            Action::Autoplay(autoplay) => {
+                player.lock().await.set_autoplay(*autoplay).await;
+                Executed::Autoplay(*autoplay)
+            }
             Action::Stop => {
                 player.lock().await.stop().await;
                 Executed::Stop
             }
+            Action::SessionRestore { .. } => {
+                let session = session_to_restore
+                    .take()
+                    .ok_or(UserCausedError::NoSessionToRestore)?;
+                let track_count = session.queue.tracks.len();
+                player.lock().await.restore_session(session).await;
+                Executed::SessionRestore { track_count }
+            }
+            Action::ImportCancel { .. }
+            | Action::Settings(_)
+            | Action::SpotifyMatchForget { .. } => {
+                unreachable!("These actions are executed without a player.")
+            }
+        })
+    }
+
+    /// The owner is either the user owning the application, or any member of its team.
+    async fn check_bot_owner(&self, context: &Context, command: &Command) -> Result<(), Error> {
+        let user_id = command.user_id.ok_or(UserCausedError::UnknownUser)?;
+        let application_info = context
+            .http
+            .get_current_application_info()
+            .await
+            .map_err(InternalError::ApplicationInfoRetrieval)?;
+
+        let is_owner = application_info
+            .owner
+            .is_some_and(|owner| owner.id == user_id)
+            || application_info.team.is_some_and(|team| {
+                team.members
+                    .iter()
+                    .any(|team_member| team_member.user.id == user_id)
+            });
+        match is_owner {
+            false => Err(UserCausedError::NotBotOwner)?,
+            true => Ok(()),
+        }
+    }
+
+    async fn execute_settings_action(
+        &self,
+        guild_id: GuildId,
+        settings_action: &SettingsAction,
+    ) -> Result<Executed, Error> {
+        self.settings_manager
+            .update_guild(guild_id, |guild_settings| match settings_action {
+                SettingsAction::VoteSkip {
+                    enabled,
+                    required_percentage,
+                } => {
+                    guild_settings.vote_skip = *enabled;
+                    if let Some(required_percentage) = required_percentage {
+                        guild_settings.vote_skip_percentage = *required_percentage;
+                    }
+                }
+                SettingsAction::DjRole(role_id) => guild_settings.dj_role_id = *role_id,
+                SettingsAction::LiveNowPlaying(live_now_playing) => {
+                    guild_settings.live_now_playing = *live_now_playing
+                }
+                SettingsAction::Permission {
+                    action_kind,
+                    requirement,
+                } => match requirement {
+                    None => {
+                        guild_settings.requirements.remove(action_kind);
+                    }
+                    Some(requirement) => {
+                        guild_settings
+                            .requirements
+                            .insert(*action_kind, *requirement);
+                    }
+                },
+                SettingsAction::Limits {
+                    max_queue_length,
+                    max_user_track_count,
+                    max_track_duration,
+                    max_playlist_import_size,
+                } => {
+                    let limits = &mut guild_settings.limits;
+                    if let Some(max_queue_length) = max_queue_length {
+                        limits.max_queue_length = *max_queue_length;
+                    }
+                    if let Some(max_user_track_count) = max_user_track_count {
+                        limits.max_user_track_count = *max_user_track_count;
+                    }
+                    if let Some(max_track_duration) = max_track_duration {
+                        limits.max_track_duration = *max_track_duration;
+                    }
+                    if let Some(max_playlist_import_size) = max_playlist_import_size {
+                        limits.max_playlist_import_size = *max_playlist_import_size;
+                    }
+                }
+            })
+            .await
+            .map_err(InternalError::SettingsSave)?;
+
+        if let SettingsAction::LiveNowPlaying(live_now_playing) = settings_action
+            && let Some(player) = self.players.lock().await.get(&guild_id)
+        {
+            player.lock().await.set_live_now_playing(*live_now_playing);
+        }
+
+        let guild_settings = self.settings_manager.guild(guild_id).await;
+        Ok(match settings_action {
+            SettingsAction::VoteSkip { .. } => Executed::VoteSkipSettings {
+                enabled: guild_settings.vote_skip,
+                percentage: guild_settings.vote_skip_percentage,
+            },
+            SettingsAction::DjRole(_) => Executed::DjRole(guild_settings.dj_role_id),
+            SettingsAction::LiveNowPlaying(_) => {
+                Executed::LiveNowPlaying(guild_settings.live_now_playing)
+            }
+            SettingsAction::Permission { action_kind, .. } => Executed::Permission {
+                action_kind: *action_kind,
+                requirement: guild_settings.requirement(*action_kind),
+            },
+            SettingsAction::Limits { .. } => Executed::Limits(guild_settings.limits),
+        })
+    }
+
+    /// Voice commands of an unrecognized speaker, or said while several users were speaking, and
+    /// the commands issued by the bot itself carry no user, so they are allowed only the actions
+    /// everyone may perform, except for the session restoration, which the bot issues on its own.
+    async fn check_permissions(
+        &self,
+        context: &Context,
+        command: &Command,
+    ) -> Result<(), UserCausedError> {
+        let action_kind = match command.action.kind() {
+            None => return Ok(()),
+            Some(action_kind) => action_kind,
+        };
+        let guild_settings = self.settings_manager.guild(command.guild_id).await;
+        let requirement = guild_settings.requirement(action_kind);
+
+        let meets_requirement = (command.user_id.is_none()
+            && action_kind == ActionKind::SessionRestore)
+            || meets_requirement(
+                context,
+                command.guild_id,
+                command.user_id,
+                requirement,
+                guild_settings.dj_role_id,
+            );
+        match meets_requirement {
+            true => Ok(()),
+            false => Err(UserCausedError::InsufficientPermissions(requirement)),
+        }
+    }
+
+    /// Returns `None` if the user may skip the current track right away, either because voting is
+    /// disabled, the user has requested the track or the user is a DJ, or because enough votes
+    /// have been cast.
+    async fn vote_skip(
+        &self,
+        context: &Context,
+        command: &Command,
+        player: &mut Player<Arc<Self>, V>,
+        round: Option<u64>,
+    ) -> Result<Option<Executed>, Error> {
+        if round.is_some_and(|round| round != player.skip_vote_round()) {
+            Err(UserCausedError::SkipVoteExpired)?;
+        }
+
+        let guild_settings = self.settings_manager.guild(command.guild_id).await;
+        if !guild_settings.vote_skip {
+            return Ok(None);
+        }
+
+        let user_id = command.user_id.ok_or(UserCausedError::UnknownUser)?;
+        if player
+            .current_track()
+            .is_some_and(|track| track.requester == Some(user_id))
+            || guild_settings
+                .dj_role_id
+                .is_some_and(|dj_role_id| has_role(context, command.guild_id, user_id, dj_role_id))
+        {
+            return Ok(None);
+        }
+
+        let voter_ids = match player.voice_channel_id().await {
+            Err(_) => Err(UserCausedError::NotPlaying)?,
+            Ok(voice_channel_id) => {
+                listener_ids(context, command.guild_id, voice_channel_id).unwrap_or_default()
+            }
+        };
+        let skip_vote = player.vote_skip(user_id, &voter_ids, guild_settings.vote_skip_percentage);
+
+        Ok(match skip_vote.votes >= skip_vote.required_votes {
+            true => None,
+            false => Some(Executed::SkipVote(skip_vote)),
         })
     }
 
@@ -272,53 +807,156 @@ impl<V: player::VoiceTickCallback> Executor<V> {
         text_channel_id: ChannelId,
         context: Context,
     ) -> Result<Arc<Mutex<Player<Arc<Self>, V>>>, player::CreationError> {
+        let guild_settings = self.settings_manager.guild(guild_id).await;
         let new_player = Player::new(
-            self.http_client.clone(),
             guild_id,
             voice_channel_id,
             text_channel_id,
             context,
-            Some(self.clone()),
+            player::Options::from(&guild_settings),
+            player::Services {
+                http_client: self.http_client.clone(),
+                session_manager: self.session_manager.clone(),
+                recommender: self.youtube_recommender.clone(),
+                track_event_callback: Some(self.clone()),
+            },
             self.voice_tick_callback.lock().await.clone(),
         )
         .await?;
-        self.players
+        let replaced_player = self
+            .players
             .lock()
             .await
             .insert(guild_id, new_player.clone());
+        // The imports of the replaced session must not enqueue into the new one.
+        if let Some(replaced_player) = replaced_player {
+            replaced_player
+                .lock()
+                .await
+                .session_cancellation_token()
+                .cancel();
+        }
 
         Ok(new_player)
     }
 
-    async fn fetch_and_enqueue_query<'a>(
-        self: &'a Arc<Self>,
+    /// Enqueues the first track of the query, leaving the rest to be enqueued in the background.
+    /// Returns `None` if nothing has been found.
+    async fn start_import(
+        self: &Arc<Self>,
         player: &Arc<Mutex<Player<Arc<Self>, V>>>,
-        query: &'a str,
-    ) -> anyhow::Result<Option<query::Fetched<'a>>> {
-        for query_fetcher in self.query_fetchers.iter() {
-            let mut fetched_query = match query_fetcher.fetch(query).await? {
-                None => continue,
-                Some(fetched_query) => fetched_query,
-            };
-
-            let mut has_fetched_any_tracks = false;
-            while let Some(track) = fetched_query.tracks.next().await {
-                match track {
-                    Err(error) => error!("{error}"),
-                    Ok(track) => {
-                        player.lock().await.enqueue(track).await;
-                        has_fetched_any_tracks = true;
-                    }
-                }
+        command: &Command,
+        source: ImportSource,
+        position: EnqueuePosition,
+        track_limit: Option<usize>,
+        limits: Limits,
+    ) -> Result<Option<Enqueued>, Error> {
+        // Cancelled along with the session of the player, so that no track is enqueued once it stops.
+        let cancellation_token = {
+            let player = player.lock().await;
+            if !matches!(position, EnqueuePosition::Back) && player.is_queue_fair() {
+                Err(UserCausedError::QueueOrderedFairly)?;
             }
-            if !has_fetched_any_tracks {
-                continue;
+            player.session_cancellation_token().child_token()
+        };
+        let import_id = self.next_import_id.fetch_add(1, Ordering::Relaxed);
+        let (started_sender, started_receiver) = oneshot::channel();
+        let (progress_sender, mut progress_receiver) = watch::channel(ImportProgress::default());
+        let import_task = ImportTask {
+            player: player.clone(),
+            position,
+            track_limit,
+            requester: command.user_id,
+            limits,
+            cancellation_token: cancellation_token.clone(),
+            started_sender: Some(started_sender),
+            progress_sender,
+        };
+
+        // Registered before the task is spawned, so that the task cannot end before that.
+        self.imports.lock().await.insert(
+            import_id,
+            Import {
+                guild_id: command.guild_id,
+                requester: command.user_id,
+                cancellation_token,
+            },
+        );
+        let executor = self.clone();
+        tokio::spawn(async move {
+            import_task.run(&executor.query_fetchers, source).await;
+            executor.imports.lock().await.remove(&import_id);
+        });
+
+        let started = started_receiver
+            .await
+            .map_err(|_| InternalError::ImportPanicked)??;
+        // A single track is done with right away, so that no progress of the import is shown.
+        if progress_receiver.borrow().total_track_count == Some(1) {
+            _ = progress_receiver
+                .wait_for(|progress| progress.state != ImportState::InProgress)
+                .await;
+        }
+        Ok(started.map(|started| Enqueued {
+            import_id,
+            title: started.title,
+            url: started.url,
+            thumbnail_url: started.thumbnail_url,
+            progress: progress_receiver,
+        }))
+    }
+
+    /// Anyone who may stop the playback may cancel the imports of the others.
+    async fn cancel_import(
+        &self,
+        context: &Context,
+        command: &Command,
+        import_id: u64,
+    ) -> Result<Executed, Error> {
+        let mut imports = self.imports.lock().await;
+        let import = imports
+            .get(&import_id)
+            .filter(|import| import.guild_id == command.guild_id)
+            .ok_or(UserCausedError::ImportNotRunning)?;
+
+        if import.requester != command.user_id {
+            let guild_settings = self.settings_manager.guild(command.guild_id).await;
+            let requirement = guild_settings.requirement(ActionKind::Stop);
+            if !meets_requirement(
+                context,
+                command.guild_id,
+                command.user_id,
+                requirement,
+                guild_settings.dj_role_id,
+            ) {
+                Err(UserCausedError::InsufficientPermissions(requirement))?;
             }
+        }
 
-            return Ok(Some(fetched_query));
+        if let Some(import) = imports.remove(&import_id) {
+            import.cancellation_token.cancel();
         }
+        Ok(Executed::ImportCancel)
+    }
 
-        Ok(None)
+    /// All the attachments are validated before any of them gets enqueued.
+    async fn resolve_attachments(&self, attachments: &[Attachment]) -> Result<Vec<Track>, Error> {
+        let mut tracks = Vec::new();
+        for attachment in attachments {
+            tracks.push(
+                match attachment::resolve(&self.http_client, attachment).await {
+                    Err(attachment::Error::TooLarge) => Err(UserCausedError::AttachmentTooLarge(
+                        attachment.filename.clone(),
+                    ))?,
+                    Err(attachment::Error::Unsupported) => Err(
+                        UserCausedError::AttachmentUnsupported(attachment.filename.clone()),
+                    )?,
+                    Err(error) => Err(InternalError::Attachment(error))?,
+                    Ok(track) => track,
+                },
+            );
+        }
+        Ok(tracks)
     }
 
     pub(crate) async fn player_text_channel_id(&self, guild_id: &GuildId) -> Option<ChannelId> {
@@ -328,6 +966,63 @@ impl<V: player::VoiceTickCallback> Executor<V> {
         }
     }
 
+    pub(crate) async fn player_playback_state(
+        &self,
+        guild_id: &GuildId,
+    ) -> Option<player::PlaybackState> {
+        match self.players.lock().await.get(guild_id) {
+            None => None,
+            Some(player) => Some(player.lock().await.playback_state().await),
+        }
+    }
+
+    pub(crate) async fn player_now_playing_message_id(
+        &self,
+        guild_id: &GuildId,
+    ) -> Option<MessageId> {
+        match self.players.lock().await.get(guild_id) {
+            None => None,
+            Some(player) => player.lock().await.now_playing_message_id(),
+        }
+    }
+
+    pub(crate) async fn player_user_id_by_ssrc(
+        &self,
+        guild_id: &GuildId,
+        ssrc: u32,
+    ) -> Option<UserId> {
+        match self.players.lock().await.get(guild_id) {
+            None => None,
+            Some(player) => player.lock().await.user_id_by_ssrc(ssrc),
+        }
+    }
+
+    /// Lets the player know whether any user is left in its voice channel.
+    pub(crate) async fn on_voice_state_update(&self, context: &Context, guild_id: GuildId) {
+        let player = match self.players.lock().await.get(&guild_id) {
+            None => return,
+            Some(player) => player.clone(),
+        };
+        let mut player = player.lock().await;
+        if player.is_stopped() {
+            return;
+        }
+
+        let player_voice_channel_id = match player.voice_channel_id().await {
+            Err(_) => return,
+            Ok(voice_channel_id) => voice_channel_id,
+        };
+        let voice_channel_is_empty = match listener_ids(context, guild_id, player_voice_channel_id)
+        {
+            None => return,
+            Some(listener_ids) => listener_ids.is_empty(),
+        };
+
+        player
+            .set_voice_channel_is_empty(voice_channel_is_empty)
+            .await;
+    }
+
     pub(crate) async fn set_voice_tick_callback(&self, voice_tick_callback: Option<V>) {
         *self.voice_tick_callback.lock().await = voice_tick_callback.clone();
 
@@ -340,24 +1035,258 @@ impl<V: player::VoiceTickCallback> Executor<V> {
     }
 }
 
+/// Returns the users other than bots in the voice channel, `None` if the guild is not cached.
+fn listener_ids(
+    context: &Context,
+    guild_id: GuildId,
+    voice_channel_id: songbird::id::ChannelId,
+) -> Option<Vec<UserId>> {
+    let current_user_id = context.cache.current_user().id;
+    let guild = guild_id.to_guild_cached(&context.cache)?;
+    Some(
+        guild
+            .voice_states
+            .values()
+            .filter(|voice_state| {
+                voice_state.channel_id.is_some_and(|channel_id| {
+                    songbird::id::ChannelId::from(channel_id) == voice_channel_id
+                }) && voice_state.user_id != current_user_id
+                    // The voice states cached on joining the guild carry no member data.
+                    && !voice_state
+                        .member
+                        .as_ref()
+                        .or_else(|| guild.members.get(&voice_state.user_id))
+                        .is_some_and(|member| member.user.bot)
+            })
+            .map(|voice_state| voice_state.user_id)
+            .collect(),
+    )
+}
+
+/// The users in a voice channel come with their member data, so the cached guild members are
+/// needed only as a fallback.
+fn has_role(context: &Context, guild_id: GuildId, user_id: UserId, role_id: RoleId) -> bool {
+    guild_id
+        .to_guild_cached(&context.cache)
+        .and_then(|guild| {
+            guild
+                .voice_states
+                .get(&user_id)
+                .and_then(|voice_state| voice_state.member.as_ref())
+                .or_else(|| guild.members.get(&user_id))
+                .map(|member| member.roles.contains(&role_id))
+        })
+        .unwrap_or(false)
+}
+
+/// The server managers meet every requirement, an unknown user meets only those met by everyone.
+fn meets_requirement(
+    context: &Context,
+    guild_id: GuildId,
+    user_id: Option<UserId>,
+    requirement: Requirement,
+    dj_role_id: Option<RoleId>,
+) -> bool {
+    let dj_role_id = match (requirement, dj_role_id) {
+        (Requirement::Everyone, _) | (Requirement::Dj, None) => return true,
+        (_, dj_role_id) => dj_role_id,
+    };
+    let user_id = match user_id {
+        None => return false,
+        Some(user_id) => user_id,
+    };
+
+    let guild = match guild_id.to_guild_cached(&context.cache) {
+        None => return false,
+        Some(guild) => guild,
+    };
+    let voice_state = guild.voice_states.get(&user_id);
+    let member = match voice_state
+        .and_then(|voice_state| voice_state.member.as_ref())
+        .or_else(|| guild.members.get(&user_id))
+    {
+        None => return false,
+        Some(member) => member,
+    };
+    // Outside of any voice channel, e.g. in the case of the text commands, the server-wide
+    // permissions apply.
+    let permissions = voice_state
+        .and_then(|voice_state| voice_state.channel_id)
+        .and_then(|channel_id| guild.channels.get(&channel_id))
+        .map(|channel| guild.user_permissions_in(channel, member))
+        .unwrap_or_else(|| guild.member_permissions(member));
+    if permissions.manage_guild() {
+        return true;
+    }
+
+    match requirement {
+        Requirement::Everyone => true,
+        Requirement::Dj => dj_role_id.is_some_and(|dj_role_id| member.roles.contains(&dj_role_id)),
+        Requirement::Role(role_id) => member.roles.contains(&role_id),
+        Requirement::Permissions(required_permissions) => {
+            permissions.contains(required_permissions)
+        }
+    }
+}
+
 #[async_trait]
-impl<V: player::VoiceTickCallback> player::TrackStartedPlayingCallback for Arc<Executor<V>> {
-    async fn on_started_playing(&self, track: Track, text_channel_id: ChannelId, context: Context) {
+impl<V: player::VoiceTickCallback> player::TrackEventCallback for Arc<Executor<V>> {
+    async fn on_started_playing(
+        &self,
+        track: Track,
+        playback_state: player::PlaybackState,
+        text_channel_id: ChannelId,
+        context: Context,
+    ) {
         _ = self
             .activity_manager
             .set_current_playing_track(track.clone())
             .await
             .log_error();
 
-        let embed =
-            embed::base("Přehrávání", EmbedIcon::YouTube, track.title).url(track.youtube_url);
-        let embed = match track.thumbnail_url {
-            None => embed,
-            Some(thumbnail_url) => embed.thumbnail(thumbnail_url),
-        };
+        let mut message =
+            CreateMessage::new().components(playback_control_components(playback_state));
+        let mut embed = embed::track_started_playing(&track);
+        if let (None, Source::LocalFile { path }) = (&track.thumbnail_url, track.source)
+            && let Ok(Some(cover_art)) =
+                tokio::task::spawn_blocking(move || library::read_cover_art(&path)).await
+        {
+            let file_name = format!("obal.{}", cover_art.file_extension());
+            embed = embed.thumbnail(format!("attachment://{file_name}"));
+            message = message.add_file(CreateAttachment::bytes(cover_art.data, file_name));
+        }
         _ = text_channel_id
-            .send_message(context.http, CreateMessage::new().embed(embed))
+            .send_message(context.http, message.embed(embed))
             .await
             .log_error();
     }
+
+    async fn on_failed_to_play(
+        &self,
+        track: Track,
+        error: PlayError,
+        text_channel_id: ChannelId,
+        context: Context,
+    ) {
+        _ = text_channel_id
+            .send_message(
+                context.http,
+                CreateMessage::new().embed(embed::track_failed_to_play(&track, &error)),
+            )
+            .await
+            .log_error();
+    }
+
+    async fn on_stream_title_changed(
+        &self,
+        track: Track,
+        stream_title: String,
+        text_channel_id: ChannelId,
+        context: Context,
+    ) {
+        self.activity_manager
+            .set_current_stream_title(stream_title.clone());
+
+        _ = text_channel_id
+            .send_message(
+                context.http,
+                CreateMessage::new().embed(embed::stream_title_changed(&track, stream_title)),
+            )
+            .await
+            .log_error();
+    }
+
+    async fn on_now_playing_changed(
+        &self,
+        now_playing: Option<player::NowPlaying>,
+        update: player::NowPlayingUpdate,
+        message_id: Option<MessageId>,
+        text_channel_id: ChannelId,
+        context: Context,
+    ) -> Option<MessageId> {
+        match (&now_playing, update) {
+            (Some(now_playing), player::NowPlayingUpdate::TrackStarted) => {
+                _ = self
+                    .activity_manager
+                    .set_current_playing_track(now_playing.track.clone())
+                    .await
+                    .log_error();
+            }
+            (Some(now_playing), player::NowPlayingUpdate::StreamTitleChanged) => {
+                if let Some(stream_title) = &now_playing.stream_title {
+                    self.activity_manager
+                        .set_current_stream_title(stream_title.clone());
+                }
+            }
+            _ => (),
+        }
+
+        let embed = embed::now_playing(now_playing.as_ref());
+        let components = match &now_playing {
+            None => Vec::new(),
+            Some(now_playing) => playback_control_components(now_playing.playback_state),
+        };
+
+        // Once buried under other messages, the message is sent again at the bottom.
+        let message_id = match message_id {
+            Some(message_id)
+                if matches!(
+                    update,
+                    player::NowPlayingUpdate::TrackStarted | player::NowPlayingUpdate::Refresh
+                ) =>
+            {
+                match text_channel_id
+                    .messages(
+                        &context.http,
+                        GetMessages::new()
+                            .after(message_id)
+                            .limit(NOW_PLAYING_MESSAGE_MAX_BURIAL_DEPTH),
+                    )
+                    .await
+                {
+                    Ok(messages)
+                        if messages.len() >= usize::from(NOW_PLAYING_MESSAGE_MAX_BURIAL_DEPTH) =>
+                    {
+                        _ = text_channel_id
+                            .delete_message(&context.http, message_id)
+                            .await
+                            .log_error();
+                        None
+                    }
+                    Ok(_) => Some(message_id),
+                    Err(error) => {
+                        error!("{error}");
+                        Some(message_id)
+                    }
+                }
+            }
+            message_id => message_id,
+        };
+
+        // A message which cannot be edited, e.g. because it has been deleted, is sent anew.
+        if let Some(message_id) = message_id
+            && text_channel_id
+                .edit_message(
+                    &context.http,
+                    message_id,
+                    EditMessage::new()
+                        .embed(embed.clone())
+                        .components(components.clone()),
+                )
+                .await
+                .log_error()
+                .is_ok()
+        {
+            return Some(message_id);
+        }
+        text_channel_id
+            .send_message(
+                &context.http,
+                CreateMessage::new().embed(embed).components(components),
+            )
+            .await
+            .log_error()
+            .ok()
+            .map(|message| message.id)
+    }
 }
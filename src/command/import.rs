@@ -0,0 +1,322 @@
+use crate::command::{Error, Executor, InternalError, Limit, UserCausedError};
+use crate::player::{EnqueuePosition, Player, Track};
+use crate::query::Fetcher;
+use crate::settings::Limits;
+use crate::{player, query};
+use log::error;
+use serenity::all::UserId;
+use std::mem;
+use std::sync::Arc;
+use tokio::sync::{Mutex, oneshot, watch};
+use tokio_util::sync::CancellationToken;
+
+/// How many tracks are inserted into the queue under a single lock of the player. The first track is
+/// inserted right away, so that it can start playing.
+const BATCH_LENGTH: usize = 10;
+
+/// The progress of enqueueing the tracks of a query in the background.
+#[derive(Clone, Default)]
+pub(crate) struct ImportProgress {
+    pub(crate) track_count: usize,
+    /// How many tracks are to be enqueued, `None` if unknown.
+    pub(crate) total_track_count: Option<usize>,
+    pub(crate) limits_reached: Vec<Limit>,
+    /// How many tracks have been left out due to the limits, `None` if unknown.
+    pub(crate) skipped_track_count: Option<usize>,
+    pub(crate) state: ImportState,
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub(crate) enum ImportState {
+    #[default]
+    InProgress,
+    Finished,
+    Cancelled,
+}
+
+/// A query whose first track has been enqueued, the rest are being enqueued in the background.
+#[derive(Clone)]
+pub(crate) struct Enqueued {
+    pub(crate) import_id: u64,
+    pub(crate) title: String,
+    pub(crate) url: Option<String>,
+    pub(crate) thumbnail_url: Option<String>,
+    pub(crate) progress: watch::Receiver<ImportProgress>,
+}
+
+/// The tracks to be enqueued, either those of a query yet to be fetched, or already fetched ones.
+pub(crate) enum ImportSource {
+    Query(String),
+    Fetched(query::Fetched<'static>),
+}
+
+/// Describes the fetched query once its first track has been enqueued.
+pub(crate) struct ImportStarted {
+    pub(crate) title: String,
+    pub(crate) url: Option<String>,
+    pub(crate) thumbnail_url: Option<String>,
+}
+
+/// Receives the outcome of the import if no track gets enqueued, `None` if nothing has been found.
+pub(crate) type ImportStartedSender = oneshot::Sender<Result<Option<ImportStarted>, Error>>;
+
+/// Enqueues the tracks of a single import and reports its progress.
+pub(crate) struct ImportTask<V: player::VoiceTickCallback> {
+    pub(crate) player: Arc<Mutex<Player<Arc<Executor<V>>, V>>>,
+    pub(crate) position: EnqueuePosition,
+    pub(crate) track_limit: Option<usize>,
+    pub(crate) requester: Option<UserId>,
+    pub(crate) limits: Limits,
+    pub(crate) cancellation_token: CancellationToken,
+    /// Taken once the first track has been enqueued.
+    pub(crate) started_sender: Option<ImportStartedSender>,
+    pub(crate) progress_sender: watch::Sender<ImportProgress>,
+}
+
+/// The state of enqueueing a single fetched query.
+#[derive(Default)]
+struct Enqueueing {
+    /// Where the next track is inserted, so that the tracks form a single block in the queue.
+    index: Option<usize>,
+    /// The tracks waiting to be inserted.
+    batch: Vec<Track>,
+    track_count: usize,
+    fetched_track_count: usize,
+    skipped_track_count: usize,
+    limits_reached: Vec<Limit>,
+}
+
+impl<V: player::VoiceTickCallback> ImportTask<V> {
+    pub(crate) async fn run(
+        mut self,
+        query_fetchers: &[Box<dyn Fetcher + Send + Sync>],
+        source: ImportSource,
+    ) {
+        let result = match source {
+            ImportSource::Query(query) => self.fetch_and_enqueue(query_fetchers, &query).await,
+            ImportSource::Fetched(fetched_query) => self
+                .enqueue_fetched(fetched_query)
+                .await
+                .map_err(Error::from),
+        };
+        let is_cancelled = self.cancellation_token.is_cancelled();
+
+        // No track has been enqueued, so the outcome is the whole reply.
+        if let Some(started_sender) = self.started_sender.take() {
+            _ = started_sender.send(match is_cancelled {
+                true => Err(UserCausedError::ImportCancelled.into()),
+                false => result.map(|_| None),
+            });
+            return;
+        }
+
+        if let Err(error) = result {
+            error!("{error}");
+        }
+        self.progress_sender.send_modify(|progress| {
+            progress.state = match is_cancelled {
+                true => ImportState::Cancelled,
+                false => ImportState::Finished,
+            }
+        });
+    }
+
+    /// Returns how many tracks have been enqueued from the first query fetcher which has found any.
+    async fn fetch_and_enqueue(
+        &mut self,
+        query_fetchers: &[Box<dyn Fetcher + Send + Sync>],
+        query: &str,
+    ) -> Result<usize, Error> {
+        for query_fetcher in query_fetchers {
+            let fetched_query = tokio::select! {
+                _ = self.cancellation_token.cancelled() => break,
+                fetched_query = query_fetcher.fetch(query) => fetched_query,
+            };
+            let fetched_query = match fetched_query.map_err(InternalError::Play)? {
+                None => continue,
+                Some(fetched_query) => fetched_query,
+            };
+
+            let track_count = self.enqueue_fetched(fetched_query).await?;
+            if track_count > 0 {
+                return Ok(track_count);
+            }
+        }
+
+        Ok(0)
+    }
+
+    /// Returns how many tracks have been enqueued. Fails if no tracks could have been enqueued due
+    /// to the limits.
+    async fn enqueue_fetched(
+        &mut self,
+        mut fetched_query: query::Fetched<'_>,
+    ) -> Result<usize, UserCausedError> {
+        let track_limit = self
+            .track_limit
+            .or(fetched_query.default_track_limit)
+            .unwrap_or(usize::MAX);
+        let total_track_count = fetched_query.track_count.map(|track_count| {
+            track_count
+                .min(track_limit)
+                .min(self.limits.max_playlist_import_size.unwrap_or(usize::MAX))
+        });
+        self.progress_sender.send_modify(|progress| {
+            *progress = ImportProgress {
+                total_track_count,
+                ..ImportProgress::default()
+            }
+        });
+
+        let mut enqueueing = Enqueueing::default();
+        while enqueueing.track_count + enqueueing.batch.len() < track_limit {
+            let track = tokio::select! {
+                _ = self.cancellation_token.cancelled() => None,
+                track = fetched_query.tracks.next() => track,
+            };
+            let track = match track {
+                None => break,
+                Some(track) => track,
+            };
+            enqueueing.fetched_track_count += 1;
+            let track = match track {
+                Err(error) => {
+                    error!("{error}");
+                    continue;
+                }
+                Ok(track) => track,
+            };
+
+            if let Some(max_playlist_import_size) = self.limits.max_playlist_import_size
+                && enqueueing.track_count + enqueueing.batch.len() >= max_playlist_import_size
+            {
+                enqueueing.skipped_track_count += 1;
+                enqueueing
+                    .limits_reached
+                    .push(Limit::PlaylistImportSize(max_playlist_import_size));
+                break;
+            }
+            if let Some(max_track_duration) = self.limits.max_track_duration
+                && track
+                    .duration
+                    .is_some_and(|duration| duration > max_track_duration)
+            {
+                enqueueing.skipped_track_count += 1;
+                if !enqueueing
+                    .limits_reached
+                    .contains(&Limit::TrackDuration(max_track_duration))
+                {
+                    enqueueing
+                        .limits_reached
+                        .push(Limit::TrackDuration(max_track_duration));
+                }
+                continue;
+            }
+
+            enqueueing.batch.push(track);
+            if (enqueueing.track_count == 0 || enqueueing.batch.len() >= BATCH_LENGTH)
+                && self.insert_batch(&mut enqueueing, &fetched_query).await
+            {
+                break;
+            }
+        }
+        if !enqueueing.batch.is_empty() {
+            self.insert_batch(&mut enqueueing, &fetched_query).await;
+        }
+
+        if enqueueing.track_count == 0
+            && let Some(limit) = enqueueing.limits_reached.first()
+        {
+            Err(UserCausedError::LimitReached(*limit))?;
+        }
+
+        // The tracks not fetched at all are known only by their total count.
+        let has_stopped_at_limit = matches!(
+            enqueueing.limits_reached.last(),
+            Some(Limit::QueueLength(_) | Limit::UserTrackCount(_) | Limit::PlaylistImportSize(_))
+        );
+        let skipped_track_count = match has_stopped_at_limit {
+            false => Some(enqueueing.skipped_track_count),
+            true => fetched_query.track_count.map(|total_track_count| {
+                enqueueing.skipped_track_count
+                    + total_track_count.saturating_sub(enqueueing.fetched_track_count)
+            }),
+        };
+        self.progress_sender.send_modify(|progress| {
+            progress.limits_reached = enqueueing.limits_reached;
+            progress.skipped_track_count = skipped_track_count;
+        });
+
+        Ok(enqueueing.track_count)
+    }
+
+    /// Inserts the batch under a single lock of the player, stopping at the first limit reached.
+    /// Returns whether the enqueueing should stop, due to a limit or the cancellation.
+    async fn insert_batch(
+        &mut self,
+        enqueueing: &mut Enqueueing,
+        fetched_query: &query::Fetched<'_>,
+    ) -> bool {
+        let batch = mem::take(&mut enqueueing.batch);
+        let batch_length = batch.len();
+        let mut inserted_track_count = 0;
+        let mut limit_reached = None;
+        {
+            let mut player = self.player.lock().await;
+            // Checked under the lock, so that no track is enqueued once the player is stopped.
+            if self.cancellation_token.is_cancelled() {
+                return true;
+            }
+            for mut track in batch {
+                let upcoming_tracks = player.upcoming_tracks();
+                if let Some(max_queue_length) = self.limits.max_queue_length
+                    && upcoming_tracks.len() >= max_queue_length
+                {
+                    limit_reached = Some(Limit::QueueLength(max_queue_length));
+                    break;
+                }
+                if let Some(max_user_track_count) = self.limits.max_user_track_count
+                    && let Some(requester) = self.requester
+                    && upcoming_tracks
+                        .iter()
+                        .filter(|track| track.requester == Some(requester))
+                        .count()
+                        >= max_user_track_count
+                {
+                    limit_reached = Some(Limit::UserTrackCount(max_user_track_count));
+                    break;
+                }
+
+                track.requester = self.requester;
+                let insertion_index = enqueueing
+                    .index
+                    .unwrap_or_else(|| player.enqueue_index(self.position));
+                enqueueing.index = Some(player.insert(insertion_index, track).await + 1);
+                inserted_track_count += 1;
+            }
+        }
+
+        enqueueing.track_count += inserted_track_count;
+        if inserted_track_count > 0 {
+            let track_count = enqueueing.track_count;
+            self.progress_sender
+                .send_modify(|progress| progress.track_count = track_count);
+            if let Some(started_sender) = self.started_sender.take() {
+                _ = started_sender.send(Ok(Some(ImportStarted {
+                    title: fetched_query.title.clone(),
+                    url: fetched_query.url.clone(),
+                    thumbnail_url: fetched_query.thumbnail_url.clone(),
+                })));
+            }
+        }
+
+        match limit_reached {
+            None => false,
+            Some(limit) => {
+                enqueueing.skipped_track_count += batch_length - inserted_track_count;
+                enqueueing.limits_reached.push(limit);
+                true
+            }
+        }
+    }
+}
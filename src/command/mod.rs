@@ -1,13 +1,25 @@
 mod execution;
+mod import;
 mod registration;
 pub(crate) mod voice;
 
+use crate::player::{EnqueuePosition, PlaybackState, SeekPosition, VolumeChange};
+use crate::query;
+use crate::settings::Requirement;
 use amplify_derive::Display;
 use deunicode::deunicode;
 pub(crate) use execution::*;
+pub(crate) use import::*;
 pub(crate) use registration::*;
-use serenity::all::{ChannelId, CommandDataOptionValue, CommandInteraction, Context, GuildId};
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    Attachment, ButtonStyle, ChannelId, CommandDataOption, CommandDataOptionValue,
+    CommandInteraction, ComponentInteraction, Context, CreateActionRow, CreateButton, GuildId,
+    ResolvedTarget, RoleId, UserId,
+};
+use std::ops::RangeInclusive;
 use std::str::FromStr;
+use std::time::Duration;
 use thiserror::Error;
 
 enum Action {
@@ -15,22 +27,203 @@ enum Action {
         text_channel_id: ChannelId,
         voice_channel_id: ChannelId,
         query: String,
+        position: EnqueuePosition,
+        /// Limits the tracks enqueued from a playlist or a podcast.
+        track_limit: Option<usize>,
     },
     VoicePlay {
         query: String,
     },
-    QueueView,
+    PlayAttachments {
+        text_channel_id: ChannelId,
+        voice_channel_id: ChannelId,
+        attachments: Vec<Attachment>,
+        position: EnqueuePosition,
+    },
+    /// Shows a page of the queue, the one with the current track if not set.
+    QueueView {
+        page: Option<usize>,
+    },
     QueueMove {
         index: usize,
     },
+    QueueRemove {
+        indices: RangeInclusive<usize>,
+    },
+    QueueReorder {
+        from_index: usize,
+        to_index: usize,
+    },
+    /// Removes the upcoming tracks enqueued by the user issuing the command.
+    QueueRemoveOwn,
     QueueRepeat(bool),
+    /// Interleaves the upcoming tracks by their requesters.
+    QueueFair(bool),
     QueueShuffle,
     Next,
     Previous,
     Pause,
     Resume,
+    Seek(SeekPosition),
+    Volume(VolumeChange),
     Repeat(bool),
+    Autoplay(bool),
     Stop,
+    SessionRestore {
+        text_channel_id: ChannelId,
+        voice_channel_id: ChannelId,
+    },
+    /// A vote to skip the current track, valid only within the given round of voting.
+    SkipVote {
+        round: u64,
+    },
+    /// Stops enqueueing the rest of the tracks of a query, does not need a player.
+    ImportCancel {
+        import_id: u64,
+    },
+    /// Changes the guild settings, does not need a player.
+    Settings(SettingsAction),
+    /// Makes the next play of the Spotify track search for it on YouTube again, does not need a
+    /// player.
+    SpotifyMatchForget {
+        spotify_track_id: String,
+    },
+}
+
+enum SettingsAction {
+    VoteSkip {
+        enabled: bool,
+        /// Keeps the current percentage if not set.
+        required_percentage: Option<u8>,
+    },
+    DjRole(Option<RoleId>),
+    LiveNowPlaying(bool),
+    Permission {
+        action_kind: ActionKind,
+        /// Resets the requirement to the default if not set.
+        requirement: Option<Requirement>,
+    },
+    /// The outer `None` keeps the current value, the inner one removes the limit.
+    Limits {
+        max_queue_length: Option<Option<usize>>,
+        max_user_track_count: Option<Option<usize>>,
+        max_track_duration: Option<Option<Duration>>,
+        max_playlist_import_size: Option<Option<usize>>,
+    },
+}
+
+/// The actions as distinguished by the permissions, e.g. all ways of playing count as one.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ActionKind {
+    Play,
+    QueueView,
+    QueueMove,
+    QueueRemove,
+    QueueReorder,
+    QueueRemoveOwn,
+    QueueRepeat,
+    QueueFair,
+    QueueShuffle,
+    Next,
+    Previous,
+    Pause,
+    Resume,
+    Seek,
+    Volume,
+    Repeat,
+    Autoplay,
+    Stop,
+    SessionRestore,
+}
+
+impl ActionKind {
+    pub(crate) const ALL: [Self; 19] = [
+        Self::Play,
+        Self::QueueView,
+        Self::QueueMove,
+        Self::QueueRemove,
+        Self::QueueReorder,
+        Self::QueueRemoveOwn,
+        Self::QueueRepeat,
+        Self::QueueFair,
+        Self::QueueShuffle,
+        Self::Next,
+        Self::Previous,
+        Self::Pause,
+        Self::Resume,
+        Self::Seek,
+        Self::Volume,
+        Self::Repeat,
+        Self::Autoplay,
+        Self::Stop,
+        Self::SessionRestore,
+    ];
+
+    /// Follows the names of the slash commands.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Play => "hrat",
+            Self::QueueView => "fronta-zobrazit",
+            Self::QueueMove => "fronta-posunout",
+            Self::QueueRemove => "fronta-odebrat",
+            Self::QueueReorder => "fronta-presunout",
+            Self::QueueRemoveOwn => "fronta-odebrat-moje",
+            Self::QueueRepeat => "fronta-opakovat",
+            Self::QueueFair => "fronta-spravedlive",
+            Self::QueueShuffle => "fronta-nahodne",
+            Self::Next => "dalsi",
+            Self::Previous => "predchozi",
+            Self::Pause => "pauza",
+            Self::Resume => "pokracovat",
+            Self::Seek => "posunout-cas",
+            Self::Volume => "hlasitost",
+            Self::Repeat => "opakovat",
+            Self::Autoplay => "autoplay",
+            Self::Stop => "stop",
+            Self::SessionRestore => "obnovit-relaci",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|action_kind| action_kind.name() == name)
+    }
+}
+
+impl Action {
+    /// In percent.
+    const VOICE_VOLUME_STEP: u16 = 20;
+
+    /// `None` for the settings, which are guarded by the Discord permissions of the command.
+    fn kind(&self) -> Option<ActionKind> {
+        Some(match self {
+            Self::Play { .. }
+            | Self::VoicePlay { .. }
+            | Self::PlayAttachments { .. }
+            | Self::ImportCancel { .. } => ActionKind::Play,
+            Self::QueueView { .. } => ActionKind::QueueView,
+            Self::QueueMove { .. } => ActionKind::QueueMove,
+            Self::QueueRemove { .. } => ActionKind::QueueRemove,
+            Self::QueueReorder { .. } => ActionKind::QueueReorder,
+            Self::QueueRemoveOwn => ActionKind::QueueRemoveOwn,
+            Self::QueueRepeat(_) => ActionKind::QueueRepeat,
+            Self::QueueFair(_) => ActionKind::QueueFair,
+            Self::QueueShuffle => ActionKind::QueueShuffle,
+            Self::Next | Self::SkipVote { .. } => ActionKind::Next,
+            Self::Previous => ActionKind::Previous,
+            Self::Pause => ActionKind::Pause,
+            Self::Resume => ActionKind::Resume,
+            Self::Seek(_) => ActionKind::Seek,
+            Self::Volume(_) => ActionKind::Volume,
+            Self::Repeat(_) => ActionKind::Repeat,
+            Self::Autoplay(_) => ActionKind::Autoplay,
+            Self::Stop => ActionKind::Stop,
+            Self::SessionRestore { .. } => ActionKind::SessionRestore,
+            Self::Settings(_) | Self::SpotifyMatchForget { .. } => return None,
+        })
+    }
 }
 
 impl FromStr for Action {
@@ -44,8 +237,21 @@ impl FromStr for Action {
             Base,
             Queue,
             QueueMove,
+            QueueRemove {
+                start_index: Option<usize>,
+            },
+            QueueReorder {
+                from_index: Option<usize>,
+            },
             QueueRepeat,
+            QueueFair,
+            Seek {
+                forward: Option<bool>,
+                offset: Option<Duration>,
+            },
+            Volume,
             Repeat,
+            Autoplay,
         }
         let mut current_stage = Stage::None;
 
@@ -83,43 +289,110 @@ impl FromStr for Action {
                         current_stage = Stage::Queue;
                     } else if word_normalized.replace("t", "d").contains("dal") {
                         return Ok(Self::Next);
+                    } else if word_normalized.replace("a", "o").contains("pretoc") {
+                        current_stage = Stage::Seek {
+                            forward: None,
+                            offset: None,
+                        };
                     } else if word_normalized.replace("t", "d").contains("pred")
                         || word_normalized.replace("s", "z").contains("hoz")
                     {
                         return Ok(Self::Previous);
+                    } else if word_normalized.contains("hlasitost") {
+                        current_stage = Stage::Volume;
+                    } else if word_normalized.contains("hlasitej")
+                        || word_normalized.contains("zesil")
+                    {
+                        return Ok(Self::Volume(VolumeChange::Increase(
+                            Self::VOICE_VOLUME_STEP,
+                        )));
+                    } else if word_normalized.contains("tisej") || word_normalized.contains("ztis")
+                    {
+                        return Ok(Self::Volume(VolumeChange::Decrease(
+                            Self::VOICE_VOLUME_STEP,
+                        )));
                     } else if word_normalized.contains("pau") {
                         return Ok(Self::Pause);
                     } else if word_normalized.replace("g", "k").contains("krac") {
                         return Ok(Self::Resume);
                     } else if word_normalized.contains("pako") {
                         current_stage = Stage::Repeat;
+                    } else if word_normalized.contains("auto") {
+                        current_stage = Stage::Autoplay;
                     } else if word_normalized.replace("d", "t").contains("top") {
                         return Ok(Self::Stop);
                     }
                 }
                 Stage::Queue => {
                     if word_normalized.replace("s", "z").contains("obraz") {
-                        return Ok(Self::QueueView);
+                        return Ok(Self::QueueView { page: None });
+                    } else if word_normalized.contains("presun") {
+                        current_stage = Stage::QueueReorder { from_index: None };
                     } else if word_normalized.contains("suno") {
                         current_stage = Stage::QueueMove;
+                    } else if word_normalized.replace("p", "b").contains("odeb") {
+                        current_stage = Stage::QueueRemove { start_index: None };
                     } else if word_normalized.contains("pako") {
                         current_stage = Stage::QueueRepeat;
+                    } else if word_normalized.contains("spravedl") {
+                        current_stage = Stage::QueueFair;
                     } else if word_normalized.replace("t", "d").contains("hod") {
                         return Ok(Self::QueueShuffle);
                     }
                 }
                 Stage::QueueMove => {
-                    if let Some(index) = word_normalized
-                        .chars()
-                        .filter(|char| char.is_numeric())
-                        .collect::<String>()
-                        .parse::<usize>()
-                        .ok()
-                        .and_then(|index| index.checked_sub(1))
-                    {
+                    if let Some(index) = parse_voice_queue_index(&word_normalized) {
                         return Ok(Self::QueueMove { index });
                     }
                 }
+                Stage::QueueRemove { start_index: None } => {
+                    if word_normalized.contains("moj") || word_normalized.starts_with("sv") {
+                        return Ok(Self::QueueRemoveOwn);
+                    } else if let Some(indices) = parse_queue_indices(
+                        &word_normalized
+                            .chars()
+                            .filter(|char| char.is_numeric() || *char == '-')
+                            .collect::<String>(),
+                    ) {
+                        if indices.start() != indices.end() {
+                            return Ok(Self::QueueRemove { indices });
+                        }
+                        current_stage = Stage::QueueRemove {
+                            start_index: Some(*indices.start()),
+                        };
+                    }
+                }
+                Stage::QueueRemove {
+                    start_index: Some(start_index),
+                } => {
+                    if let Some(end_index) = parse_voice_queue_index(&word_normalized) {
+                        return Ok(Self::QueueRemove {
+                            indices: start_index.min(end_index)..=start_index.max(end_index),
+                        });
+                    } else if !(word_normalized.contains("az")
+                        || word_normalized == "do"
+                        || word_normalized == "-")
+                    {
+                        break;
+                    }
+                }
+                Stage::QueueReorder { from_index: None } => {
+                    if let Some(from_index) = parse_voice_queue_index(&word_normalized) {
+                        current_stage = Stage::QueueReorder {
+                            from_index: Some(from_index),
+                        };
+                    }
+                }
+                Stage::QueueReorder {
+                    from_index: Some(from_index),
+                } => {
+                    if let Some(to_index) = parse_voice_queue_index(&word_normalized) {
+                        return Ok(Self::QueueReorder {
+                            from_index,
+                            to_index,
+                        });
+                    }
+                }
                 Stage::QueueRepeat => {
                     if word_normalized.contains("ano") {
                         return Ok(Self::QueueRepeat(true));
@@ -127,6 +400,61 @@ impl FromStr for Action {
                         return Ok(Self::QueueRepeat(false));
                     }
                 }
+                Stage::QueueFair => {
+                    if word_normalized.contains("ano") {
+                        return Ok(Self::QueueFair(true));
+                    } else if word_normalized.contains("ne") {
+                        return Ok(Self::QueueFair(false));
+                    }
+                }
+                Stage::Seek {
+                    ref mut forward,
+                    ref mut offset,
+                } => {
+                    let number = word_normalized
+                        .chars()
+                        .filter(|char| char.is_numeric() || *char == ':')
+                        .collect::<String>();
+
+                    if number.contains(':') {
+                        return parse_duration(&number)
+                            .map(SeekPosition::Absolute)
+                            .map(Self::Seek)
+                            .ok_or(());
+                    } else if let Ok(seconds) = number.parse::<u64>() {
+                        *offset = Some(Duration::from_secs(seconds));
+                    } else if word_normalized.contains("minut")
+                        && let Some(minutes) = offset
+                    {
+                        *minutes *= 60;
+                    } else if word_normalized.contains("dopred")
+                        || word_normalized.contains("vpred")
+                    {
+                        *forward = Some(true);
+                    } else if word_normalized.contains("dozad")
+                        || word_normalized.replace("a", "e").contains("zpet")
+                    {
+                        *forward = Some(false);
+                    }
+                }
+                Stage::Volume => {
+                    if let Ok(volume) = word_normalized
+                        .chars()
+                        .filter(|char| char.is_numeric())
+                        .collect::<String>()
+                        .parse::<u16>()
+                    {
+                        return Ok(Self::Volume(VolumeChange::Set(volume)));
+                    } else if word_normalized.contains("nahor") || word_normalized.contains("vys") {
+                        return Ok(Self::Volume(VolumeChange::Increase(
+                            Self::VOICE_VOLUME_STEP,
+                        )));
+                    } else if word_normalized.contains("dolu") || word_normalized.contains("niz") {
+                        return Ok(Self::Volume(VolumeChange::Decrease(
+                            Self::VOICE_VOLUME_STEP,
+                        )));
+                    }
+                }
                 Stage::Repeat => {
                     if word_normalized.contains("ano") {
                         return Ok(Self::Repeat(true));
@@ -134,13 +462,143 @@ impl FromStr for Action {
                         return Ok(Self::Repeat(false));
                     }
                 }
+                Stage::Autoplay => {
+                    if word_normalized.contains("ano") {
+                        return Ok(Self::Autoplay(true));
+                    } else if word_normalized.contains("ne") {
+                        return Ok(Self::Autoplay(false));
+                    }
+                }
             }
         }
 
-        Err(())
+        match current_stage {
+            Stage::QueueRemove {
+                start_index: Some(start_index),
+            } => Ok(Self::QueueRemove {
+                indices: start_index..=start_index,
+            }),
+            Stage::Seek {
+                forward,
+                offset: Some(offset),
+            } => Ok(Self::Seek(match forward {
+                None => SeekPosition::Absolute(offset),
+                Some(true) => SeekPosition::Forward(offset),
+                Some(false) => SeekPosition::Backward(offset),
+            })),
+            _ => Err(()),
+        }
     }
 }
 
+fn find_option_value<'a>(
+    command_data_options: &'a [CommandDataOption],
+    name: &str,
+) -> Option<&'a CommandDataOptionValue> {
+    command_data_options
+        .iter()
+        .find(|command_data_option| command_data_option.name == name)
+        .map(|command_data_option| &command_data_option.value)
+}
+
+/// Zero stands for no limit.
+fn limit_option_value(
+    command_data_options: &[CommandDataOption],
+    name: &str,
+) -> Result<Option<Option<usize>>, FromInteractionError> {
+    Ok(match find_option_value(command_data_options, name) {
+        None => None,
+        Some(CommandDataOptionValue::Integer(0)) => Some(None),
+        Some(CommandDataOptionValue::Integer(value)) => Some(Some(
+            usize::try_from(*value).map_err(|_| FromInteractionInternalError::InvalidOption)?,
+        )),
+        Some(_) => Err(FromInteractionInternalError::InvalidOption)?,
+    })
+}
+
+fn parse_voice_queue_index(word: &str) -> Option<usize> {
+    word.chars()
+        .filter(|char| char.is_numeric())
+        .collect::<String>()
+        .parse::<usize>()
+        .ok()
+        .and_then(|index| index.checked_sub(1))
+}
+
+/// Parses a queue position (`5`) or an inclusive range of positions (`5-20`), both numbered from
+/// 1, into zero-based indices.
+fn parse_queue_indices(text: &str) -> Option<RangeInclusive<usize>> {
+    let parse_index = |text: &str| {
+        text.trim()
+            .parse::<usize>()
+            .ok()
+            .and_then(|position| position.checked_sub(1))
+    };
+
+    match text.split_once('-') {
+        None => parse_index(text).map(|index| index..=index),
+        Some((start, end)) => {
+            let (start_index, end_index) = (parse_index(start)?, parse_index(end)?);
+            Some(start_index.min(end_index)..=start_index.max(end_index))
+        }
+    }
+}
+
+/// Parses an absolute (`1:23`) or a relative (`+30s`, `-10s`, `+1m30s`) track position.
+fn parse_seek_position(text: &str) -> Option<SeekPosition> {
+    let text = text.trim();
+
+    if let Some(offset) = text.strip_prefix('+') {
+        parse_duration(offset).map(SeekPosition::Forward)
+    } else if let Some(offset) = text.strip_prefix('-') {
+        parse_duration(offset).map(SeekPosition::Backward)
+    } else {
+        parse_duration(text).map(SeekPosition::Absolute)
+    }
+}
+
+/// Parses either a colon-separated (`1:02:03`) or a unit-suffixed (`1h2m3s`) duration. A bare
+/// number is interpreted as seconds.
+fn parse_duration(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+
+    let seconds = if text.contains(':') {
+        let parts = text.split(':').collect::<Vec<_>>();
+        if parts.len() > 3 {
+            return None;
+        }
+        parts.into_iter().try_fold(0u64, |seconds, part| {
+            seconds.checked_mul(60)?.checked_add(part.parse().ok()?)
+        })?
+    } else {
+        let mut seconds = 0u64;
+        let mut number = String::new();
+        for char in text.chars() {
+            if char.is_ascii_digit() {
+                number.push(char);
+                continue;
+            }
+            let multiplier = match char.to_ascii_lowercase() {
+                'h' => 60 * 60,
+                'm' => 60,
+                's' => 1,
+                _ => return None,
+            };
+            seconds = seconds.checked_add(number.parse::<u64>().ok()?.checked_mul(multiplier)?)?;
+            number.clear();
+        }
+        if !number.is_empty() {
+            seconds = seconds.checked_add(number.parse().ok()?)?;
+        }
+        seconds
+    };
+
+    Some(Duration::from_secs(seconds))
+}
+
 #[derive(Error, Display, Debug)]
 #[display(Debug)]
 #[allow(dead_code)]
@@ -166,6 +624,12 @@ impl From<FromInteractionInternalError> for FromInteractionError {
 pub(crate) enum FromInteractionUserCausedError {
     NotInGuild,
     UserNotInVoiceChannel,
+    InvalidQueuePositions,
+    InvalidSeekPosition,
+    MissingPlayQuery,
+    NoAttachments,
+    MissingRole,
+    InvalidSpotifyTrack,
 }
 
 #[derive(Error, Display, Debug)]
@@ -177,18 +641,114 @@ pub(crate) enum FromInteractionInternalError {
 
 pub(crate) struct Command {
     guild_id: GuildId,
+    /// Not set for commands not issued by a user, e.g. the automatic session restoration, or when
+    /// the speaker of a voice command is not known.
+    user_id: Option<UserId>,
     /// Not set for voice commands.
     voice_channel_id: Option<ChannelId>,
     text_channel_id: Option<ChannelId>,
     action: Action,
 }
 
+/// The custom ID of the button offering to resume the session interrupted by a bot restart.
+pub(crate) const SESSION_RESTORE_BUTTON_ID: &str = "obnovit-relaci";
+/// Followed by the round of voting in the custom ID of the button voting to skip the current track.
+const SKIP_VOTE_BUTTON_ID_PREFIX: &str = "preskocit-hlas-";
+
+/* The custom IDs of the buttons controlling the playback, attached to the message announcing the
+current track. */
+const PREVIOUS_BUTTON_ID: &str = "predchozi";
+const PAUSE_BUTTON_ID: &str = "pozastavit";
+const RESUME_BUTTON_ID: &str = "pokracovat";
+const NEXT_BUTTON_ID: &str = "dalsi";
+const REPEAT_ON_BUTTON_ID: &str = "opakovat-zapnout";
+const REPEAT_OFF_BUTTON_ID: &str = "opakovat-vypnout";
+const STOP_BUTTON_ID: &str = "stop";
+/// Followed by the role of the button and the page in the custom IDs of the buttons navigating the
+/// queue view, since the custom IDs within a message have to be unique.
+const QUEUE_VIEW_PAGE_BUTTON_ID_PREFIX: &str = "fronta-strana-";
+const QUEUE_VIEW_CURRENT_PAGE_BUTTON_ID: &str = "fronta-aktualni";
+/// Followed by the import in the custom ID of the button cancelling the enqueueing of a query.
+const IMPORT_CANCEL_BUTTON_ID_PREFIX: &str = "zrusit-zarazovani-";
+
+pub(crate) fn skip_vote_button_id(round: u64) -> String {
+    format!("{SKIP_VOTE_BUTTON_ID_PREFIX}{round}")
+}
+
+/// The buttons toggling the pause and the repetition show the action they perform.
+pub(crate) fn playback_control_components(playback_state: PlaybackState) -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(PREVIOUS_BUTTON_ID)
+            .label("Předchozí")
+            .style(ButtonStyle::Secondary),
+        match playback_state.paused {
+            false => CreateButton::new(PAUSE_BUTTON_ID)
+                .label("Pozastavit")
+                .style(ButtonStyle::Primary),
+            true => CreateButton::new(RESUME_BUTTON_ID)
+                .label("Pokračovat")
+                .style(ButtonStyle::Success),
+        },
+        CreateButton::new(NEXT_BUTTON_ID)
+            .label("Další")
+            .style(ButtonStyle::Secondary),
+        match playback_state.repeating {
+            false => CreateButton::new(REPEAT_ON_BUTTON_ID)
+                .label("Opakovat")
+                .style(ButtonStyle::Secondary),
+            true => CreateButton::new(REPEAT_OFF_BUTTON_ID)
+                .label("Neopakovat")
+                .style(ButtonStyle::Success),
+        },
+        CreateButton::new(STOP_BUTTON_ID)
+            .label("Zastavit")
+            .style(ButtonStyle::Danger),
+    ])]
+}
+
+/// The buttons are shown only if there are multiple pages.
+pub(crate) fn queue_view_components(page: usize, page_count: usize) -> Vec<CreateActionRow> {
+    if page_count <= 1 {
+        return Vec::new();
+    }
+
+    let page_button = |role: &str, label: &str, target_page: usize, disabled: bool| {
+        CreateButton::new(format!(
+            "{QUEUE_VIEW_PAGE_BUTTON_ID_PREFIX}{role}-{target_page}"
+        ))
+        .label(label)
+        .style(ButtonStyle::Secondary)
+        .disabled(disabled)
+    };
+    let is_first = page == 0;
+    let is_last = page + 1 >= page_count;
+    vec![CreateActionRow::Buttons(vec![
+        page_button("prvni", "První", 0, is_first),
+        page_button("predchozi", "Předchozí", page.saturating_sub(1), is_first),
+        CreateButton::new(QUEUE_VIEW_CURRENT_PAGE_BUTTON_ID)
+            .label("Aktuální")
+            .style(ButtonStyle::Primary),
+        page_button("dalsi", "Další", page + 1, is_last),
+        page_button("posledni", "Poslední", page_count - 1, is_last),
+    ])]
+}
+
+pub(crate) fn import_components(import_id: u64) -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("{IMPORT_CANCEL_BUTTON_ID_PREFIX}{import_id}"))
+            .label("Zrušit zařazování")
+            .style(ButtonStyle::Danger),
+    ])]
+}
+
 impl Command {
-    pub(crate) async fn try_from_interaction(
-        command_interaction: &CommandInteraction,
+    /// Returns the guild and the voice channel the user is in.
+    async fn user_voice_channel_id(
+        guild_id: Option<GuildId>,
+        user_id: UserId,
         context: &Context,
-    ) -> Result<Command, FromInteractionError> {
-        let guild = match command_interaction.guild_id {
+    ) -> Result<(GuildId, ChannelId), FromInteractionError> {
+        let guild = match guild_id {
             None => Err(FromInteractionUserCausedError::NotInGuild)?,
             Some(guild_id) => guild_id
                 .to_guild_cached(context.cache.as_ref())
@@ -196,43 +756,130 @@ impl Command {
                 .clone(),
         };
 
-        let voice_channel_id = match guild
+        match guild
             .voice_states
-            .get(&command_interaction.user.id)
+            .get(&user_id)
             .and_then(|voice_state| voice_state.channel_id)
         {
             None => Err(FromInteractionUserCausedError::UserNotInVoiceChannel)?,
-            Some(channel_id) => channel_id,
-        };
+            Some(channel_id) => Ok((guild.id, channel_id)),
+        }
+    }
+
+    pub(crate) async fn try_from_interaction(
+        command_interaction: &CommandInteraction,
+        context: &Context,
+    ) -> Result<Command, FromInteractionError> {
+        // The settings do not require the user to be in a voice channel.
+        if command_interaction.data.name == "nastaveni" {
+            return Self::try_from_settings_interaction(command_interaction);
+        }
+
+        let (guild_id, voice_channel_id) = Self::user_voice_channel_id(
+            command_interaction.guild_id,
+            command_interaction.user.id,
+            context,
+        )
+        .await?;
 
-        let command_data_option = command_interaction.data.options.first();
+        let command_data_options = &command_interaction.data.options;
+        let command_data_option = command_data_options.first();
         let action = match command_interaction.data.name.as_str() {
             "hrat" => {
-                let query = command_data_option
-                    .and_then(|command_data_option| match &command_data_option.value {
-                        CommandDataOptionValue::String(value) => Some(value),
-                        _ => None,
+                let query =
+                    find_option_value(command_data_options, "hledani").and_then(
+                        |value| match value {
+                            CommandDataOptionValue::String(value) => Some(value),
+                            _ => None,
+                        },
+                    );
+                let attachment = find_option_value(command_data_options, "soubor")
+                    .map(|value| match value {
+                        CommandDataOptionValue::Attachment(attachment_id) => command_interaction
+                            .data
+                            .resolved
+                            .attachments
+                            .get(attachment_id)
+                            .ok_or(FromInteractionInternalError::InvalidOption),
+                        _ => Err(FromInteractionInternalError::InvalidOption),
                     })
-                    .ok_or(FromInteractionInternalError::InvalidOption)?;
-                Action::Play {
+                    .transpose()?;
+                let position = match (
+                    find_option_value(command_data_options, "pozice"),
+                    find_option_value(command_data_options, "hned-dalsi"),
+                ) {
+                    (Some(CommandDataOptionValue::Integer(position)), _) => EnqueuePosition::Index(
+                        position
+                            .checked_sub(1)
+                            .and_then(|index| usize::try_from(index).ok())
+                            .ok_or(FromInteractionInternalError::InvalidOption)?,
+                    ),
+                    (_, Some(CommandDataOptionValue::Boolean(true))) => EnqueuePosition::Next,
+                    _ => EnqueuePosition::Back,
+                };
+                match (attachment, query) {
+                    (Some(attachment), _) => Action::PlayAttachments {
+                        text_channel_id: command_interaction.channel_id,
+                        voice_channel_id,
+                        attachments: vec![attachment.clone()],
+                        position,
+                    },
+                    (None, Some(query)) => Action::Play {
+                        text_channel_id: command_interaction.channel_id,
+                        voice_channel_id,
+                        query: query.clone(),
+                        position,
+                        track_limit: match find_option_value(command_data_options, "pocet") {
+                            Some(CommandDataOptionValue::Integer(track_limit)) => Some(
+                                usize::try_from(*track_limit)
+                                    .map_err(|_| FromInteractionInternalError::InvalidOption)?,
+                            ),
+                            _ => None,
+                        },
+                    },
+                    (None, None) => Err(FromInteractionUserCausedError::MissingPlayQuery)?,
+                }
+            }
+            "Přehrát" => {
+                let attachments = match command_interaction.data.target() {
+                    Some(ResolvedTarget::Message(message)) => message.attachments.clone(),
+                    _ => Err(FromInteractionInternalError::InvalidOption)?,
+                };
+                if attachments.is_empty() {
+                    Err(FromInteractionUserCausedError::NoAttachments)?;
+                }
+                Action::PlayAttachments {
                     text_channel_id: command_interaction.channel_id,
                     voice_channel_id,
-                    query: query.clone(),
+                    attachments,
+                    position: EnqueuePosition::Back,
                 }
             }
             "fronta" => {
                 let command_data_option =
                     command_data_option.ok_or(FromInteractionInternalError::InvalidOption)?;
-                let subcommand_data_option_value = match &command_data_option.value {
+                let subcommand_data_options = match &command_data_option.value {
                     CommandDataOptionValue::SubCommand(subcommand_data_options) => {
                         subcommand_data_options
-                            .first()
-                            .map(|subcommand_data_option| &subcommand_data_option.value)
                     }
                     _ => Err(FromInteractionInternalError::InvalidOption)?,
                 };
+                let subcommand_data_option_value = subcommand_data_options
+                    .first()
+                    .map(|subcommand_data_option| &subcommand_data_option.value);
                 match command_data_option.name.as_str() {
-                    "zobrazit" => Action::QueueView,
+                    "zobrazit" => Action::QueueView {
+                        page: match subcommand_data_option_value {
+                            None => None,
+                            Some(CommandDataOptionValue::Integer(value)) => Some(
+                                value
+                                    .checked_sub(1)
+                                    .and_then(|value| usize::try_from(value).ok())
+                                    .ok_or(FromInteractionInternalError::InvalidOption)?,
+                            ),
+                            Some(_) => Err(FromInteractionInternalError::InvalidOption)?,
+                        },
+                    },
                     "posunout" => {
                         let index = subcommand_data_option_value
                             .and_then(|subcommand_data_option_value| {
@@ -246,6 +893,38 @@ impl Command {
                             .ok_or(FromInteractionInternalError::InvalidOption)?;
                         Action::QueueMove { index }
                     }
+                    "odebrat" => {
+                        let positions = subcommand_data_option_value
+                            .and_then(|subcommand_data_option_value| {
+                                match subcommand_data_option_value {
+                                    CommandDataOptionValue::String(value) => Some(value),
+                                    _ => None,
+                                }
+                            })
+                            .ok_or(FromInteractionInternalError::InvalidOption)?;
+                        Action::QueueRemove {
+                            indices: parse_queue_indices(positions)
+                                .ok_or(FromInteractionUserCausedError::InvalidQueuePositions)?,
+                        }
+                    }
+                    "presunout" => {
+                        let [from_index, to_index] = ["z", "na"].map(|name| {
+                            find_option_value(subcommand_data_options, name).and_then(|value| {
+                                match value {
+                                    CommandDataOptionValue::Integer(value) => value
+                                        .checked_sub(1)
+                                        .and_then(|value| usize::try_from(value).ok()),
+                                    _ => None,
+                                }
+                            })
+                        });
+                        Action::QueueReorder {
+                            from_index: from_index
+                                .ok_or(FromInteractionInternalError::InvalidOption)?,
+                            to_index: to_index
+                                .ok_or(FromInteractionInternalError::InvalidOption)?,
+                        }
+                    }
                     "opakovat" => {
                         let repeat = subcommand_data_option_value
                             .and_then(|subcommand_data_option_value| {
@@ -257,6 +936,18 @@ impl Command {
                             .ok_or(FromInteractionInternalError::InvalidOption)?;
                         Action::QueueRepeat(repeat)
                     }
+                    "spravedlive" => {
+                        let fair = subcommand_data_option_value
+                            .and_then(|subcommand_data_option_value| {
+                                match subcommand_data_option_value {
+                                    CommandDataOptionValue::Boolean(value) => Some(*value),
+                                    _ => None,
+                                }
+                            })
+                            .ok_or(FromInteractionInternalError::InvalidOption)?;
+                        Action::QueueFair(fair)
+                    }
+                    "odebrat-moje" => Action::QueueRemoveOwn,
                     "nahodne" => Action::QueueShuffle,
                     _ => Err(FromInteractionInternalError::InvalidOption)?,
                 }
@@ -265,6 +956,27 @@ impl Command {
             "predchozi" => Action::Previous,
             "pauza" => Action::Pause,
             "pokracovat" => Action::Resume,
+            "posunout-cas" => {
+                let position = command_data_option
+                    .and_then(|command_data_option| match &command_data_option.value {
+                        CommandDataOptionValue::String(value) => Some(value),
+                        _ => None,
+                    })
+                    .ok_or(FromInteractionInternalError::InvalidOption)?;
+                Action::Seek(
+                    parse_seek_position(position)
+                        .ok_or(FromInteractionUserCausedError::InvalidSeekPosition)?,
+                )
+            }
+            "hlasitost" => {
+                let volume = command_data_option
+                    .and_then(|command_data_option| match command_data_option.value {
+                        CommandDataOptionValue::Integer(value) => u16::try_from(value).ok(),
+                        _ => None,
+                    })
+                    .ok_or(FromInteractionInternalError::InvalidOption)?;
+                Action::Volume(VolumeChange::Set(volume))
+            }
             "opakovat" => {
                 let repeat = command_data_option
                     .and_then(|command_data_option| match command_data_option.value {
@@ -274,24 +986,271 @@ impl Command {
                     .ok_or(FromInteractionInternalError::InvalidOption)?;
                 Action::Repeat(repeat)
             }
+            "autoplay" => {
+                let autoplay = command_data_option
+                    .and_then(|command_data_option| match command_data_option.value {
+                        CommandDataOptionValue::Boolean(value) => Some(value),
+                        _ => None,
+                    })
+                    .ok_or(FromInteractionInternalError::InvalidOption)?;
+                Action::Autoplay(autoplay)
+            }
             "stop" => Action::Stop,
             _ => Err(FromInteractionInternalError::InvalidOption)?,
         };
 
         Ok(Self {
-            guild_id: guild.id,
+            guild_id,
+            user_id: Some(command_interaction.user.id),
             voice_channel_id: Some(voice_channel_id),
             text_channel_id: Some(command_interaction.channel_id),
             action,
         })
     }
 
-    pub(crate) fn try_from_text(text: impl AsRef<str>, guild_id: GuildId) -> Result<Self, ()> {
+    fn try_from_settings_interaction(
+        command_interaction: &CommandInteraction,
+    ) -> Result<Command, FromInteractionError> {
+        let guild_id = command_interaction
+            .guild_id
+            .ok_or(FromInteractionUserCausedError::NotInGuild)?;
+
+        let command_data_option = command_interaction
+            .data
+            .options
+            .first()
+            .ok_or(FromInteractionInternalError::InvalidOption)?;
+        let subcommand_data_options = match &command_data_option.value {
+            CommandDataOptionValue::SubCommand(subcommand_data_options) => subcommand_data_options,
+            _ => Err(FromInteractionInternalError::InvalidOption)?,
+        };
+        // The cache of the Spotify matches is shared by all the guilds, not a setting of one, so
+        // only the owner of the bot may forget them.
+        if command_data_option.name == "zapomenout-spotify" {
+            let spotify_track_id = match find_option_value(subcommand_data_options, "odkaz") {
+                Some(CommandDataOptionValue::String(text)) => query::spotify::parse_track_id(text)
+                    .ok_or(FromInteractionUserCausedError::InvalidSpotifyTrack)?,
+                _ => Err(FromInteractionInternalError::InvalidOption)?,
+            };
+            return Ok(Self {
+                guild_id,
+                user_id: Some(command_interaction.user.id),
+                voice_channel_id: None,
+                text_channel_id: Some(command_interaction.channel_id),
+                action: Action::SpotifyMatchForget { spotify_track_id },
+            });
+        }
+
+        let settings_action = match command_data_option.name.as_str() {
+            "hlasovani-o-preskoceni" => SettingsAction::VoteSkip {
+                enabled: match find_option_value(subcommand_data_options, "zapnout") {
+                    Some(CommandDataOptionValue::Boolean(value)) => *value,
+                    _ => Err(FromInteractionInternalError::InvalidOption)?,
+                },
+                required_percentage: match find_option_value(subcommand_data_options, "podil") {
+                    None => None,
+                    Some(CommandDataOptionValue::Integer(value)) => Some(
+                        u8::try_from(*value)
+                            .map_err(|_| FromInteractionInternalError::InvalidOption)?,
+                    ),
+                    Some(_) => Err(FromInteractionInternalError::InvalidOption)?,
+                },
+            },
+            "role-dj" => {
+                SettingsAction::DjRole(match find_option_value(subcommand_data_options, "role") {
+                    None => None,
+                    Some(CommandDataOptionValue::Role(role_id)) => Some(*role_id),
+                    Some(_) => Err(FromInteractionInternalError::InvalidOption)?,
+                })
+            }
+            "zprava-prehravani" => SettingsAction::LiveNowPlaying(
+                match find_option_value(subcommand_data_options, "jedna") {
+                    Some(CommandDataOptionValue::Boolean(value)) => *value,
+                    _ => Err(FromInteractionInternalError::InvalidOption)?,
+                },
+            ),
+            "opravneni" => {
+                let action_kind = match find_option_value(subcommand_data_options, "prikaz") {
+                    Some(CommandDataOptionValue::String(name)) => ActionKind::from_name(name)
+                        .ok_or(FromInteractionInternalError::InvalidOption)?,
+                    _ => Err(FromInteractionInternalError::InvalidOption)?,
+                };
+                let role_id = match find_option_value(subcommand_data_options, "role") {
+                    None => None,
+                    Some(CommandDataOptionValue::Role(role_id)) => Some(*role_id),
+                    Some(_) => Err(FromInteractionInternalError::InvalidOption)?,
+                };
+                let requirement = match find_option_value(subcommand_data_options, "povoleni") {
+                    Some(CommandDataOptionValue::String(name)) => match name.as_str() {
+                        "vychozi" => None,
+                        "role" => Some(Requirement::Role(
+                            role_id.ok_or(FromInteractionUserCausedError::MissingRole)?,
+                        )),
+                        name => Some(
+                            Requirement::from_name(name)
+                                .ok_or(FromInteractionInternalError::InvalidOption)?,
+                        ),
+                    },
+                    _ => Err(FromInteractionInternalError::InvalidOption)?,
+                };
+                SettingsAction::Permission {
+                    action_kind,
+                    requirement,
+                }
+            }
+            "limity" => SettingsAction::Limits {
+                max_queue_length: limit_option_value(subcommand_data_options, "delka-fronty")?,
+                max_user_track_count: limit_option_value(
+                    subcommand_data_options,
+                    "polozek-na-uzivatele",
+                )?,
+                max_track_duration: limit_option_value(subcommand_data_options, "delka-polozky")?
+                    .map(|minutes| minutes.map(|minutes| Duration::from_secs(minutes as u64 * 60))),
+                max_playlist_import_size: limit_option_value(
+                    subcommand_data_options,
+                    "velikost-playlistu",
+                )?,
+            },
+            _ => Err(FromInteractionInternalError::InvalidOption)?,
+        };
+
         Ok(Self {
             guild_id,
+            user_id: Some(command_interaction.user.id),
+            voice_channel_id: None,
+            text_channel_id: Some(command_interaction.channel_id),
+            action: Action::Settings(settings_action),
+        })
+    }
+
+    pub(crate) async fn try_from_component_interaction(
+        component_interaction: &ComponentInteraction,
+        context: &Context,
+    ) -> Result<Command, FromInteractionError> {
+        let (guild_id, voice_channel_id) = Self::user_voice_channel_id(
+            component_interaction.guild_id,
+            component_interaction.user.id,
+            context,
+        )
+        .await?;
+
+        let action = match component_interaction.data.custom_id.as_str() {
+            SESSION_RESTORE_BUTTON_ID => Action::SessionRestore {
+                text_channel_id: component_interaction.channel_id,
+                voice_channel_id,
+            },
+            PREVIOUS_BUTTON_ID => Action::Previous,
+            PAUSE_BUTTON_ID => Action::Pause,
+            RESUME_BUTTON_ID => Action::Resume,
+            NEXT_BUTTON_ID => Action::Next,
+            REPEAT_ON_BUTTON_ID => Action::Repeat(true),
+            REPEAT_OFF_BUTTON_ID => Action::Repeat(false),
+            STOP_BUTTON_ID => Action::Stop,
+            QUEUE_VIEW_CURRENT_PAGE_BUTTON_ID => Action::QueueView { page: None },
+            custom_id => {
+                if let Some(round) = custom_id
+                    .strip_prefix(SKIP_VOTE_BUTTON_ID_PREFIX)
+                    .and_then(|round| round.parse().ok())
+                {
+                    Action::SkipVote { round }
+                } else if let Some(page) = custom_id
+                    .strip_prefix(QUEUE_VIEW_PAGE_BUTTON_ID_PREFIX)
+                    .and_then(|role_and_page| role_and_page.rsplit_once('-'))
+                    .and_then(|(_, page)| page.parse().ok())
+                {
+                    Action::QueueView { page: Some(page) }
+                } else if let Some(import_id) = custom_id
+                    .strip_prefix(IMPORT_CANCEL_BUTTON_ID_PREFIX)
+                    .and_then(|import_id| import_id.parse().ok())
+                {
+                    Action::ImportCancel { import_id }
+                } else {
+                    Err(FromInteractionInternalError::InvalidOption)?
+                }
+            }
+        };
+
+        Ok(Self {
+            guild_id,
+            user_id: Some(component_interaction.user.id),
+            voice_channel_id: Some(voice_channel_id),
+            text_channel_id: Some(component_interaction.channel_id),
+            action,
+        })
+    }
+
+    /// Whether the action is one of those performed by the buttons controlling the playback.
+    pub(crate) fn is_playback_control(&self) -> bool {
+        matches!(
+            self.action,
+            Action::Previous
+                | Action::Pause
+                | Action::Resume
+                | Action::Next
+                | Action::Repeat(_)
+                | Action::Stop
+        )
+    }
+
+    pub(crate) fn is_import_cancel(&self) -> bool {
+        matches!(self.action, Action::ImportCancel { .. })
+    }
+
+    /// Used to resume a session without any user interaction.
+    pub(crate) fn session_restore(
+        guild_id: GuildId,
+        voice_channel_id: ChannelId,
+        text_channel_id: ChannelId,
+    ) -> Self {
+        Self {
+            guild_id,
+            user_id: None,
+            voice_channel_id: Some(voice_channel_id),
+            text_channel_id: Some(text_channel_id),
+            action: Action::SessionRestore {
+                text_channel_id,
+                voice_channel_id,
+            },
+        }
+    }
+
+    pub(crate) fn try_from_text(
+        text: impl AsRef<str>,
+        guild_id: GuildId,
+        user_id: Option<UserId>,
+    ) -> Result<Self, ()> {
+        Ok(Self {
+            guild_id,
+            user_id,
             voice_channel_id: None,
             text_channel_id: None,
             action: Action::from_str(text.as_ref())?,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_queue_indices_single_position() {
+        assert_eq!(parse_queue_indices("5"), Some(4..=4));
+        assert_eq!(parse_queue_indices(" 1 "), Some(0..=0));
+    }
+
+    #[test]
+    fn parse_queue_indices_range() {
+        assert_eq!(parse_queue_indices("5-20"), Some(4..=19));
+        assert_eq!(parse_queue_indices("20 - 5"), Some(4..=19));
+    }
+
+    #[test]
+    fn parse_queue_indices_invalid() {
+        assert_eq!(parse_queue_indices("0"), None);
+        assert_eq!(parse_queue_indices("1-0"), None);
+        assert_eq!(parse_queue_indices("a"), None);
+        assert_eq!(parse_queue_indices("1-"), None);
+        assert_eq!(parse_queue_indices(""), None);
+    }
+}
@@ -1,15 +1,35 @@
-use serenity::all::{Command, CommandOptionType, Context, CreateCommand, CreateCommandOption};
+use crate::command::ActionKind;
+use crate::player;
+use serenity::all::{
+    Command, CommandOptionType, CommandType, Context, CreateCommand, CreateCommandOption,
+    Permissions,
+};
 
 pub(crate) async fn register_global_commands(context: &Context) -> serenity::Result<Vec<Command>> {
     Command::set_global_commands(&context.http, vec![
         CreateCommand::new("hrat")
-            .description("Zařadí do fronty položku z odkazu nebo hledání.")
+            .description("Zařadí do fronty položku z odkazu, hledání nebo přiloženého souboru.")
             .set_options(vec![
                 CreateCommandOption::new(
                     CommandOptionType::String, "hledani", "odkaz nebo text k vyhledání",
-                ).required(true)
+                ),
+                CreateCommandOption::new(
+                    CommandOptionType::Attachment, "soubor", "zvukový soubor k přehrání",
+                ),
+                CreateCommandOption::new(
+                    CommandOptionType::Integer, "pozice", "pozice ve frontě, na kterou zařadit",
+                ).min_int_value(1),
+                CreateCommandOption::new(
+                    CommandOptionType::Boolean, "hned-dalsi", "zda zařadit hned za aktuální položku",
+                ),
+                CreateCommandOption::new(
+                    CommandOptionType::Integer, "pocet", "kolik položek playlistu či epizod podcastu zařadit",
+                ).min_int_value(1),
             ])
             .dm_permission(false),
+        CreateCommand::new("Přehrát")
+            .kind(CommandType::Message)
+            .dm_permission(false),
         CreateCommand::new("dalsi")
             .description("Přeskočí přehrávání na další pozici ve frontě.")
             .dm_permission(false),
@@ -22,8 +42,14 @@ pub(crate) async fn register_global_commands(context: &Context) -> serenity::Res
                 CreateCommandOption::new(
                     CommandOptionType::SubCommand,
                     "zobrazit",
-                    "Vypíše všechny položky ve frontě.",
-                ),
+                    "Vypíše položky ve frontě po stranách.",
+                ).set_sub_options(vec![
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "strana",
+                        "strana fronty, bez zadání ta s aktuální položkou",
+                    ).min_int_value(1),
+                ]),
                 CreateCommandOption::new(
                     CommandOptionType::SubCommand,
                     "posunout",
@@ -35,6 +61,38 @@ pub(crate) async fn register_global_commands(context: &Context) -> serenity::Res
                         "pozice ve frontě k posunutí",
                     ).required(true).min_int_value(1),
                 ]),
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "presunout",
+                    "Přesune položku ve frontě na jinou pozici bez přerušení přehrávání.",
+                ).set_sub_options(vec![
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "z",
+                        "pozice položky k přesunutí",
+                    ).required(true).min_int_value(1),
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "na",
+                        "nová pozice položky",
+                    ).required(true).min_int_value(1),
+                ]),
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "odebrat",
+                    "Odebere z fronty zadanou pozici nebo rozsah pozic.",
+                ).set_sub_options(vec![
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "pozice",
+                        "pozice nebo rozsah pozic k odebrání (např. 5 nebo 5-20)",
+                    ).required(true),
+                ]),
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "odebrat-moje",
+                    "Odebere z fronty všechny vámi zařazené položky, které ještě nezazněly.",
+                ),
                 CreateCommandOption::new(
                     CommandOptionType::SubCommand,
                     "opakovat",
@@ -46,6 +104,17 @@ pub(crate) async fn register_global_commands(context: &Context) -> serenity::Res
                         "zda zapnout opakování",
                     ).required(true),
                 ]),
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "spravedlive",
+                    "Zapne nebo vypne střídání položek fronty podle toho, kdo je zařadil.",
+                ).set_sub_options(vec![
+                    CreateCommandOption::new(
+                        CommandOptionType::Boolean,
+                        "zapnout",
+                        "zda zapnout střídání",
+                    ).required(true),
+                ]),
                 CreateCommandOption::new(
                     CommandOptionType::SubCommand,
                     "nahodne",
@@ -59,6 +128,26 @@ pub(crate) async fn register_global_commands(context: &Context) -> serenity::Res
         CreateCommand::new("pokracovat")
             .description("Znovu spustí pozastavené přehrávání.")
             .dm_permission(false),
+        CreateCommand::new("posunout-cas")
+            .description("Posune přehrávání aktuální položky na zadaný čas.")
+            .set_options(vec![
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "cas",
+                    "čas (např. 1:23) nebo posun vpřed či vzad (např. +30s, -10s)",
+                ).required(true)
+            ])
+            .dm_permission(false),
+        CreateCommand::new("hlasitost")
+            .description("Nastaví hlasitost přehrávání na tomto serveru.")
+            .set_options(vec![
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "uroven",
+                    "hlasitost v procentech",
+                ).required(true).min_int_value(0).max_int_value(player::MAX_VOLUME.into())
+            ])
+            .dm_permission(false),
         CreateCommand::new("opakovat")
             .description("Zapne nebo vypne opakování aktuální položky.")
             .set_options(vec![
@@ -69,8 +158,131 @@ pub(crate) async fn register_global_commands(context: &Context) -> serenity::Res
                 ).required(true)
             ])
             .dm_permission(false),
+        CreateCommand::new("autoplay")
+            .description("Zapne nebo vypne automatické přehrávání podobných položek po konci fronty.")
+            .set_options(vec![
+                CreateCommandOption::new(
+                    CommandOptionType::Boolean,
+                    "zapnout",
+                    "zda zapnout automatické přehrávání",
+                ).required(true)
+            ])
+            .dm_permission(false),
         CreateCommand::new("stop")
             .description("Zastaví přehrávání, odstraní všechny položky ve frontě a opustí hlasový kanál.")
             .dm_permission(false),
+        CreateCommand::new("nastaveni")
+            .description("Slouží ke změně nastavení bota na serveru.")
+            .set_options(vec![
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "hlasovani-o-preskoceni",
+                    "Zapne nebo vypne přeskakování položek hlasováním posluchačů.",
+                ).set_sub_options(vec![
+                    CreateCommandOption::new(
+                        CommandOptionType::Boolean,
+                        "zapnout",
+                        "zda zapnout hlasování",
+                    ).required(true),
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "podil",
+                        "kolik procent posluchačů musí hlasovat pro přeskočení",
+                    ).min_int_value(1).max_int_value(100),
+                ]),
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "role-dj",
+                    "Nastaví roli DJ, jejíž členové ovládají přehrávání bez omezení.",
+                ).set_sub_options(vec![
+                    CreateCommandOption::new(
+                        CommandOptionType::Role,
+                        "role",
+                        "role DJ, bez zadání se role DJ zruší",
+                    ),
+                ]),
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "zprava-prehravani",
+                    "Nastaví, zda se pro každou položku posílá nová zpráva, nebo se upravuje jediná.",
+                ).set_sub_options(vec![
+                    CreateCommandOption::new(
+                        CommandOptionType::Boolean,
+                        "jedna",
+                        "zda upravovat jedinou zprávu s průběhem přehrávání a následujícími položkami",
+                    ).required(true),
+                ]),
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "opravneni",
+                    "Nastaví, kdo smí použít zadaný příkaz.",
+                ).set_sub_options(vec![
+                    ActionKind::ALL.into_iter().fold(
+                        CreateCommandOption::new(
+                            CommandOptionType::String,
+                            "prikaz",
+                            "příkaz, jehož oprávnění nastavit",
+                        ).required(true),
+                        |option, action_kind| {
+                            option.add_string_choice(action_kind.name(), action_kind.name())
+                        },
+                    ),
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "povoleni",
+                        "kdo smí příkaz použít",
+                    ).required(true)
+                        .add_string_choice("všichni", "vsichni")
+                        .add_string_choice("role DJ", "dj")
+                        .add_string_choice("zadaná role", "role")
+                        .add_string_choice("moderátoři (oprávnění přesouvat členy)", "moderatori")
+                        .add_string_choice("správci serveru", "spravci")
+                        .add_string_choice("výchozí nastavení", "vychozi"),
+                    CreateCommandOption::new(
+                        CommandOptionType::Role,
+                        "role",
+                        "role, jejíž členové smí příkaz použít",
+                    ),
+                ]),
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "limity",
+                    "Nastaví limity fronty, nula limit zruší, nezadané limity zůstanou beze změny.",
+                ).set_sub_options(vec![
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "delka-fronty",
+                        "kolik nepřehraných položek smí fronta obsahovat",
+                    ).min_int_value(0),
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "polozek-na-uzivatele",
+                        "kolik nepřehraných položek smí mít ve frontě jeden uživatel",
+                    ).min_int_value(0),
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "delka-polozky",
+                        "kolik minut smí trvat jedna položka",
+                    ).min_int_value(0),
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "velikost-playlistu",
+                        "kolik položek lze najednou zařadit z playlistu",
+                    ).min_int_value(0),
+                ]),
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "zapomenout-spotify",
+                    "Zapomene položku nalezenou pro skladbu ze Spotify, příště se vyhledá znovu. Jen pro vlastníka bota.",
+                ).set_sub_options(vec![
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "odkaz",
+                        "odkaz na skladbu na Spotify",
+                    ).required(true),
+                ]),
+            ])
+            .default_member_permissions(Permissions::MANAGE_GUILD)
+            .dm_permission(false),
     ]).await
 }
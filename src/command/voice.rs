@@ -23,17 +23,29 @@ pub(crate) const SAMPLE_RATE_RAW: u32 = 16_000;
 struct TranscriptionQueueItem {
     samples: Vec<f32>,
     guild_id: GuildId,
+    /// The SSRC of the user who was the only one speaking during the transcribed audio, `None` if
+    /// several users spoke, since the command could have been said by any of them.
+    speaker_ssrc: Option<u32>,
 }
 
 impl TranscriptionQueueItem {
-    pub(crate) fn new(samples: Vec<f32>, guild_id: GuildId) -> Self {
-        Self { samples, guild_id }
+    pub(crate) fn new(samples: Vec<f32>, guild_id: GuildId, speaker_ssrc: Option<u32>) -> Self {
+        Self {
+            samples,
+            guild_id,
+            speaker_ssrc,
+        }
     }
 }
 
 #[async_trait]
 pub(crate) trait VoiceTranscribedCallback: Send + Sync + Clone + 'static {
-    async fn on_voice_transcribed(&self, guild_id: GuildId, text: String);
+    async fn on_voice_transcribed(
+        &self,
+        guild_id: GuildId,
+        speaker_ssrc: Option<u32>,
+        text: String,
+    );
 }
 
 #[derive(Error, Display, Debug)]
@@ -48,6 +60,7 @@ pub(crate) enum TranscriptorCreationError {
 
 pub(crate) struct Transcriptor<V: VoiceTranscribedCallback> {
     buffers: RwLock<HashMap<GuildId, Mutex<CircularQueue<i16>>>>,
+    speech_sample_counts: Mutex<HashMap<GuildId, HashMap<u32, usize>>>,
     current_silence_sample_count: Mutex<usize>,
     queue: Mutex<VecDeque<TranscriptionQueueItem>>,
     model: Whisper,
@@ -69,6 +82,7 @@ impl<V: VoiceTranscribedCallback> Transcriptor<V> {
         let new = Arc::new(Self {
             voice_transcribed_callback: Mutex::new(voice_transcribed_callback),
             buffers: RwLock::new(HashMap::new()),
+            speech_sample_counts: Mutex::new(HashMap::new()),
             current_silence_sample_count: Mutex::new(0),
             queue: Mutex::new(VecDeque::new()),
             model: Self::create_transcription_model().await?,
@@ -186,6 +200,22 @@ impl<V: VoiceTranscribedCallback> Transcriptor<V> {
                 })
                 .collect::<Vec<_>>();
 
+            {
+                let mut speech_sample_counts = self.speech_sample_counts.lock().await;
+                let speech_sample_counts = speech_sample_counts.entry(guild_id).or_default();
+                for (ssrc, voice_data) in &voice_tick.speaking {
+                    let Some(decoded_voice) = voice_data.decoded_voice.as_ref() else {
+                        continue;
+                    };
+                    if decoded_voice
+                        .iter()
+                        .any(|sample| sample.abs() > Self::SILENCE_SAMPLE_THRESHOLD)
+                    {
+                        *speech_sample_counts.entry(*ssrc).or_default() += decoded_voice.len();
+                    }
+                }
+            }
+
             let mixed_decoded_voice = Self::mix_audio_sources(decoded_voices).into_iter();
             let mixed_decoded_voice_length = mixed_decoded_voice.len();
 
@@ -218,6 +248,19 @@ impl<V: VoiceTranscribedCallback> Transcriptor<V> {
                             clone
                         };
 
+                        let speaker_ssrc = self
+                            .speech_sample_counts
+                            .lock()
+                            .await
+                            .remove(&guild_id)
+                            .and_then(|speech_sample_counts| {
+                                let mut ssrcs = speech_sample_counts.into_keys();
+                                match (ssrcs.next(), ssrcs.next()) {
+                                    (Some(ssrc), None) => Some(ssrc),
+                                    _ => None,
+                                }
+                            });
+
                         let queue_item = {
                             let mut buffer = buffer.lock().await;
                             let queue_item = TranscriptionQueueItem::new(
@@ -230,6 +273,7 @@ impl<V: VoiceTranscribedCallback> Transcriptor<V> {
                                     .map(cpal::Sample::to_float_sample)
                                     .collect(),
                                 guild_id,
+                                speaker_ssrc,
                             );
                             buffer.clear();
                             queue_item
@@ -297,7 +341,11 @@ impl<V: VoiceTranscribedCallback> Transcriptor<V> {
                     let voice_transcribed_callback = voice_transcribed_callback.clone();
                     tokio::spawn(async move {
                         voice_transcribed_callback
-                            .on_voice_transcribed(queue_item.guild_id, text)
+                            .on_voice_transcribed(
+                                queue_item.guild_id,
+                                queue_item.speaker_ssrc,
+                                text,
+                            )
                             .await
                     });
                 }
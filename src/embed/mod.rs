@@ -1,10 +1,18 @@
 use crate::command::{FromInteractionInternalError, FromInteractionUserCausedError};
-use crate::{command, player};
+use crate::model::Source;
+use crate::settings::Requirement;
+use crate::{attachment, command, player, session};
 use serenity::builder::{CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter};
 use serenity::model::Color;
+use serenity::model::Permissions;
+use serenity::model::mention::Mentionable;
+use songbird::error::PlayError;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 const ICONS_BASE_URL: &str = "https://files.matousvolf.cz/public/tranzistorak/icons/";
-const QUEUE_VIEW_MAX_TRACKS: usize = 15;
+const TRACK_FAILED_TO_PLAY_MAX_DETAILS_LENGTH: usize = 1_000;
+/// In characters.
+const PROGRESS_BAR_LENGTH: usize = 20;
 
 pub(crate) fn base(
     author_text: impl Into<String>,
@@ -28,9 +36,286 @@ pub(crate) fn command_generic_error() -> CreateEmbed {
     error("Chyba", "Při vykonávání příkazu nastala chyba.")
 }
 
+pub(crate) fn session_restore_offer(session: &session::Session) -> CreateEmbed {
+    base(
+        "Relace",
+        EmbedIcon::Queue,
+        "Přehrávání bylo přerušeno restartem. Obnovit předchozí relaci?",
+    )
+    .description(format!(
+        "*položek ve frontě: {}*",
+        session.queue.tracks.len()
+    ))
+}
+
+pub(crate) fn track_started_playing(track: &player::Track) -> CreateEmbed {
+    let mut embed = base(
+        "Přehrávání",
+        EmbedIcon::from(&track.source),
+        track.title.clone(),
+    );
+    if let Some(url) = track.url() {
+        embed = embed.url(url);
+    }
+    if let Some(thumbnail_url) = &track.thumbnail_url {
+        embed = embed.thumbnail(thumbnail_url);
+    }
+
+    let details = track_details(track);
+    if details.is_empty() {
+        embed
+    } else {
+        embed.description(details.join("\n"))
+    }
+}
+
+/// The live message announcing the current track, `None` once nothing is playing.
+pub(crate) fn now_playing(now_playing: Option<&player::NowPlaying>) -> CreateEmbed {
+    let now_playing = match now_playing {
+        None => return base("Přehrávání", EmbedIcon::Stop, "Nic se nepřehrává."),
+        Some(now_playing) => now_playing,
+    };
+    let track = &now_playing.track;
+
+    let mut embed = base(
+        "Přehrávání",
+        EmbedIcon::from(&track.source),
+        now_playing
+            .stream_title
+            .clone()
+            .unwrap_or_else(|| track.title.clone()),
+    );
+    if let Some(url) = track.url() {
+        embed = embed.url(url);
+    }
+    if let Some(thumbnail_url) = &track.thumbnail_url {
+        embed = embed.thumbnail(thumbnail_url);
+    }
+
+    let mut lines = Vec::new();
+    if now_playing.stream_title.is_some() {
+        lines.push(format!("*stanice: {}*", track.title));
+    }
+    lines.extend(track_details(track));
+
+    lines.push(match track.duration {
+        None => format!("`{}`", format_duration(now_playing.position)),
+        Some(duration) => format!(
+            "`{} {} {}`",
+            format_duration(now_playing.position),
+            progress_bar(now_playing.position, duration),
+            format_duration(duration)
+        ),
+    });
+    let states = [
+        now_playing.playback_state.paused.then_some("pozastaveno"),
+        now_playing
+            .playback_state
+            .repeating
+            .then_some("opakování položky"),
+        now_playing.repeating_queue.then_some("opakování fronty"),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+    if !states.is_empty() {
+        lines.push(format!("*{}*", states.join(", ")));
+    }
+
+    if !now_playing.upcoming_tracks.is_empty() {
+        lines.push("**Následující:**".to_owned());
+        lines.extend(
+            now_playing
+                .upcoming_tracks
+                .iter()
+                .map(|track| format!("– {}", track.title)),
+        );
+        let remaining_track_count =
+            now_playing.upcoming_track_count - now_playing.upcoming_tracks.len();
+        if remaining_track_count > 0 {
+            lines.push(format!("*a dalších: {remaining_track_count}*"));
+        }
+    }
+
+    embed.description(lines.join("\n"))
+}
+
+fn track_details(track: &player::Track) -> Vec<String> {
+    [
+        track
+            .artist
+            .as_ref()
+            .map(|artist| format!("*interpret: {artist}*")),
+        track
+            .album
+            .as_ref()
+            .map(|album| format!("*album: {album}*")),
+        track
+            .duration
+            .map(|duration| format!("*délka: {}*", format_duration(duration))),
+        track
+            .published
+            .and_then(format_date)
+            .map(|published| format!("*vydáno: {published}*")),
+        track
+            .requester
+            .map(|requester| format!("*přidal(a): {}*", requester.mention())),
+        track
+            .original_url
+            .as_ref()
+            .map(|original_url| format!("[*původní odkaz*]({original_url})")),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// The rest of the current track and all the upcoming ones, and whether some of them have an
+/// unknown duration, e.g. live streams.
+fn remaining_duration(
+    status: &player::QueueStatus,
+    current_playing_track_index: usize,
+) -> (Duration, bool) {
+    let mut has_unknown_durations = false;
+    let remaining_duration = status
+        .queue
+        .tracks
+        .iter()
+        .skip(current_playing_track_index)
+        .enumerate()
+        .filter_map(|(index, track)| {
+            has_unknown_durations |= track.duration.is_none();
+            match index {
+                0 => track
+                    .duration
+                    .map(|duration| duration.saturating_sub(status.position)),
+                _ => track.duration,
+            }
+        })
+        .sum();
+    (remaining_duration, has_unknown_durations)
+}
+
+fn progress_bar(position: Duration, duration: Duration) -> String {
+    let progress = match duration.is_zero() {
+        true => 0.0,
+        false => (position.as_secs_f64() / duration.as_secs_f64()).min(1.0),
+    };
+    let filled_length = (progress * PROGRESS_BAR_LENGTH as f64).round() as usize;
+    format!(
+        "{}{}",
+        "▰".repeat(filled_length),
+        "▱".repeat(PROGRESS_BAR_LENGTH - filled_length)
+    )
+}
+
+/// Reflects the progress of enqueueing the rest of the tracks in the background.
+pub(crate) fn enqueued(enqueued: &command::Enqueued) -> CreateEmbed {
+    let progress = enqueued.progress.borrow();
+    let mut description = match progress.state {
+        command::ImportState::InProgress => match progress.total_track_count {
+            None => format!("*zařazuji položky: {}…*\n", progress.track_count),
+            Some(total_track_count) => format!(
+                "*zařazuji položky: {} z {total_track_count}…*\n",
+                progress.track_count
+            ),
+        },
+        command::ImportState::Cancelled => format!(
+            "*zařazování zrušeno, zařazeno položek: {}*\n",
+            progress.track_count
+        ),
+        command::ImportState::Finished
+            if progress.track_count > 1 || !progress.limits_reached.is_empty() =>
+        {
+            format!("*zařazeno položek: {}*\n", progress.track_count)
+        }
+        command::ImportState::Finished => String::new(),
+    };
+    if !progress.limits_reached.is_empty() {
+        description.push_str(
+            match progress.skipped_track_count {
+                None => "*některé položky byly vynechány*\n".to_owned(),
+                Some(skipped_track_count) => {
+                    format!("*vynecháno položek: {skipped_track_count}*\n")
+                }
+            }
+            .as_str(),
+        );
+        for limit in &progress.limits_reached {
+            description.push_str(format!("*{}*\n", limit_description(*limit)).as_str());
+        }
+    }
+
+    let mut embed = base("Přidáno do fronty", EmbedIcon::Queue, &enqueued.title);
+    if !description.is_empty() {
+        embed = embed.description(description);
+    }
+    if let Some(url) = &enqueued.url {
+        embed = embed.url(url);
+    }
+    match &enqueued.thumbnail_url {
+        None => embed,
+        Some(thumbnail_url) => embed.thumbnail(thumbnail_url),
+    }
+}
+
+pub(crate) fn stream_title_changed(track: &player::Track, stream_title: String) -> CreateEmbed {
+    let embed = base("Přehrávání", EmbedIcon::from(&track.source), stream_title)
+        .description(format!("*stanice: {}*", track.title));
+    match track.url() {
+        None => embed,
+        Some(url) => embed.url(url),
+    }
+}
+
+pub(crate) fn track_failed_to_play(track: &player::Track, play_error: &PlayError) -> CreateEmbed {
+    let reason = match play_error {
+        PlayError::Create(_) => {
+            "Položku se nepodařilo načíst, mohla být odstraněna, mít věkové omezení nebo být v této \
+            oblasti nedostupná."
+        }
+        PlayError::Parse(_) => "Formát položky nebyl rozpoznán.",
+        PlayError::Decode(_) => "Položku se nepodařilo dekódovat.",
+        _ => "Položku se nepodařilo přehrát.",
+    };
+    let details = play_error
+        .to_string()
+        .chars()
+        .take(TRACK_FAILED_TO_PLAY_MAX_DETAILS_LENGTH)
+        .collect::<String>();
+
+    let embed = error("Přehrávání", format!("Položka přeskočena: {}", track.title))
+        .description(format!("{reason}\n```{details}```"));
+    match track.url() {
+        None => embed,
+        Some(url) => embed.url(url),
+    }
+}
+
+/// Rendered by Discord in the time zone and format of the user.
+fn format_date(time: SystemTime) -> Option<String> {
+    time.duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|duration| format!("<t:{}:d>", duration.as_secs()))
+}
+
+pub(crate) fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
 pub(crate) enum EmbedIcon {
     Bot,
     YouTube,
+    File,
+    Stream,
+    Attachment,
     Error,
     Queue,
     Next,
@@ -38,13 +323,15 @@ pub(crate) enum EmbedIcon {
     Pause,
     Resume,
     Repeat,
+    Volume,
     Stop,
 }
 
 impl EmbedIcon {
     fn url(&self) -> String {
         let icon = match self {
-            EmbedIcon::Bot => "bot",
+            // The sources other than YouTube have no icons of their own.
+            EmbedIcon::Bot | EmbedIcon::File | EmbedIcon::Stream | EmbedIcon::Attachment => "bot",
             EmbedIcon::YouTube => "youtube",
             EmbedIcon::Error => "error",
             EmbedIcon::Queue => "queue",
@@ -53,6 +340,7 @@ impl EmbedIcon {
             EmbedIcon::Pause => "pause",
             EmbedIcon::Resume => "resume",
             EmbedIcon::Repeat => "repeat",
+            EmbedIcon::Volume => "volume",
             EmbedIcon::Stop => "stop",
         };
 
@@ -60,6 +348,17 @@ impl EmbedIcon {
     }
 }
 
+impl From<&Source> for EmbedIcon {
+    fn from(source: &Source) -> Self {
+        match source {
+            Source::YouTube { .. } => EmbedIcon::YouTube,
+            Source::LocalFile { .. } => EmbedIcon::File,
+            Source::HttpStream { .. } => EmbedIcon::Stream,
+            Source::DiscordAttachment { .. } => EmbedIcon::Attachment,
+        }
+    }
+}
+
 impl From<FromInteractionUserCausedError> for CreateEmbed {
     fn from(from_interaction_user_caused_error: FromInteractionUserCausedError) -> Self {
         error(
@@ -72,6 +371,26 @@ impl From<FromInteractionUserCausedError> for CreateEmbed {
                 FromInteractionUserCausedError::UserNotInVoiceChannel => {
                     "Pro ovládání je nutné se připojit do hlasového kanálu.".to_owned()
                 }
+                FromInteractionUserCausedError::InvalidSeekPosition => {
+                    "Čas je nutné zadat jako pozici (např. 1:23) nebo posun (např. +30s, -10s)."
+                        .to_owned()
+                }
+                FromInteractionUserCausedError::InvalidQueuePositions => {
+                    "Pozice ve frontě je nutné zadat jako číslo (např. 5) nebo rozsah (např. 5-20)."
+                        .to_owned()
+                }
+                FromInteractionUserCausedError::MissingPlayQuery => {
+                    "Je nutné zadat odkaz či text k vyhledání, nebo přiložit soubor.".to_owned()
+                }
+                FromInteractionUserCausedError::NoAttachments => {
+                    "Zpráva neobsahuje žádné přiložené soubory.".to_owned()
+                }
+                FromInteractionUserCausedError::MissingRole => {
+                    "Pro povolení příkazu zadané roli je nutné roli zadat.".to_owned()
+                }
+                FromInteractionUserCausedError::InvalidSpotifyTrack => {
+                    "Je nutné zadat odkaz na skladbu na Spotify.".to_owned()
+                }
             },
         )
     }
@@ -83,8 +402,58 @@ impl From<FromInteractionInternalError> for CreateEmbed {
     }
 }
 
+fn requirement_description(requirement: Requirement) -> String {
+    match requirement {
+        Requirement::Everyone => "všichni".to_owned(),
+        Requirement::Dj => "členové role DJ".to_owned(),
+        Requirement::Role(role_id) => format!("členové role {}", role_id.mention()),
+        Requirement::Permissions(permissions) if permissions == Permissions::MOVE_MEMBERS => {
+            "moderátoři (oprávnění přesouvat členy)".to_owned()
+        }
+        Requirement::Permissions(permissions) if permissions == Permissions::MANAGE_GUILD => {
+            "správci serveru".to_owned()
+        }
+        Requirement::Permissions(permissions) => format!(
+            "členové s oprávněními {}",
+            permissions.get_permission_names().join(", ")
+        ),
+    }
+}
+
+fn limit_description(limit: command::Limit) -> String {
+    match limit {
+        command::Limit::QueueLength(max_queue_length) => {
+            format!("fronta smí obsahovat nejvýše {max_queue_length} nepřehraných položek")
+        }
+        command::Limit::UserTrackCount(max_user_track_count) => {
+            format!("každý smí mít ve frontě nejvýše {max_user_track_count} nepřehraných položek")
+        }
+        command::Limit::TrackDuration(max_track_duration) => format!(
+            "položky smí trvat nejvýše {}",
+            format_duration(max_track_duration)
+        ),
+        command::Limit::PlaylistImportSize(max_playlist_import_size) => {
+            format!("z playlistu lze najednou zařadit nejvýše {max_playlist_import_size} položek")
+        }
+    }
+}
+
+fn limit_value_description(limit: Option<usize>) -> String {
+    match limit {
+        None => "bez omezení".to_owned(),
+        Some(limit) => limit.to_string(),
+    }
+}
+
 impl From<command::UserCausedError> for CreateEmbed {
     fn from(user_caused_error: command::UserCausedError) -> Self {
+        if let command::UserCausedError::InsufficientPermissions(requirement) = user_caused_error {
+            return error("Chyba", "Nedostatečná oprávnění.").description(format!(
+                "*příkaz smí použít: {}*",
+                requirement_description(requirement)
+            ));
+        }
+
         error(
             "Chyba",
             match user_caused_error {
@@ -101,12 +470,70 @@ impl From<command::UserCausedError> for CreateEmbed {
                 ) => {
                     format!("Fronta {}. pozici neobsahuje.", index + 1)
                 }
+                command::UserCausedError::QueueRemove(
+                    player::QueueRemoveIndexExceedsQueueLengthError(index),
+                ) => {
+                    format!("Fronta {}. pozici neobsahuje.", index + 1)
+                }
+                command::UserCausedError::QueueReorder(
+                    player::QueueReorderIndexExceedsQueueLengthError(index),
+                ) => {
+                    format!("Fronta {}. pozici neobsahuje.", index + 1)
+                }
                 command::UserCausedError::Next(player::NextNoTrackError) => {
                     "Ve frontě se nenachází žádné další položky.".to_owned()
                 }
                 command::UserCausedError::Previous(player::PreviousNoTrackError) => {
                     "Ve frontě se nenachází žádné předchozí položky.".to_owned()
                 }
+                command::UserCausedError::AlreadyPlaying => {
+                    "Přehrávání již probíhá, předchozí relaci není možné obnovit.".to_owned()
+                }
+                command::UserCausedError::NoSessionToRestore => {
+                    "Žádná předchozí relace k obnovení neexistuje.".to_owned()
+                }
+                command::UserCausedError::SeekUnsupported => {
+                    "Aktuální položka neumožňuje posun v čase (např. jde o živé vysílání)."
+                        .to_owned()
+                }
+                command::UserCausedError::AttachmentTooLarge(file_name) => format!(
+                    "Soubor {file_name} je příliš velký, nejvýše lze přehrát {} MB.",
+                    attachment::MAX_SIZE / 1024 / 1024
+                ),
+                command::UserCausedError::AttachmentUnsupported(file_name) => {
+                    format!("Soubor {file_name} není podporovaný zvukový soubor.")
+                }
+                command::UserCausedError::UnknownUser => {
+                    "Nebylo možné rozpoznat, kdo příkaz zadal.".to_owned()
+                }
+                command::UserCausedError::SkipVoteExpired => {
+                    "Hlasování se týkalo již dříve přehrávané položky.".to_owned()
+                }
+                command::UserCausedError::InsufficientPermissions(_) => {
+                    "Nedostatečná oprávnění.".to_owned()
+                }
+                command::UserCausedError::LimitReached(limit) => {
+                    format!("Do fronty nelze nic zařadit, {}.", limit_description(limit))
+                }
+                command::UserCausedError::QueueOrderedFairly => {
+                    "Položky fronty se střídají podle toho, kdo je zařadil, jejich pořadí proto \
+                    nelze určit. Střídání lze vypnout příkazem /fronta spravedlive."
+                        .to_owned()
+                }
+                command::UserCausedError::ImportCancelled => {
+                    "Zařazování položek bylo zrušeno.".to_owned()
+                }
+                command::UserCausedError::SpotifyMatchNotCached => {
+                    "Pro zadanou skladbu není uložena žádná nalezená položka.".to_owned()
+                }
+                command::UserCausedError::NotBotOwner => {
+                    "Nalezené položky jsou společné všem serverům, zapomenout je může jen \
+                    vlastník bota."
+                        .to_owned()
+                }
+                command::UserCausedError::ImportNotRunning => {
+                    "Zařazování položek již skončilo.".to_owned()
+                }
             },
         )
     }
@@ -118,71 +545,165 @@ impl From<command::InternalError> for CreateEmbed {
     }
 }
 
-impl From<command::Executed<'_>> for CreateEmbed {
+impl From<command::Executed> for CreateEmbed {
     fn from(executed_command: command::Executed) -> Self {
         match executed_command {
             command::Executed::Play(None) => error(
                 "Nenalezeno",
                 "Dle zadaného textu nebyl nalezen žádný výsledek.",
             ),
-            command::Executed::Play(Some(fetched_query)) => {
-                let embed = base("Přidáno do fronty", EmbedIcon::Queue, fetched_query.title)
-                    .url(fetched_query.url);
-                match fetched_query.thumbnail_url {
-                    None => embed,
-                    Some(thumbnail_url) => embed.thumbnail(thumbnail_url),
-                }
-            }
-            command::Executed::QueueView(queue) => {
-                let index = queue.current_playing_track_index.unwrap_or(0);
-                let start = (index as i32 - QUEUE_VIEW_MAX_TRACKS as i32).max(0) as usize;
-                let end = (index + QUEUE_VIEW_MAX_TRACKS).min(queue.tracks.len());
-
-                let mut queue_text = String::new();
+            command::Executed::Play(Some(enqueued)) => self::enqueued(&enqueued),
+            command::Executed::ImportCancel => base(
+                "Ovládání",
+                EmbedIcon::Stop,
+                "Zařazování položek zrušeno.",
+            ),
+            command::Executed::QueueView {
+                status,
+                page,
+                page_count,
+            } => {
+                let queue = &status.queue;
+                let start = page * command::QUEUE_VIEW_PAGE_LENGTH;
+                let end = (start + command::QUEUE_VIEW_PAGE_LENGTH).min(queue.tracks.len());
 
-                if start > 0 {
-                    queue_text.push_str(format!("*předcházejících: {}*\n", start).as_str());
+                let mut lines = queue
+                    .tracks
+                    .iter()
+                    .enumerate()
+                    .skip(start)
+                    .take(end - start)
+                    .map(|(index, track)| {
+                        let title = match queue.current_playing_track_index {
+                            Some(current_playing_track_index)
+                                if index == current_playing_track_index =>
+                            {
+                                format!("**{}** ", track.title)
+                            }
+                            None | Some(_) => track.title.clone(),
+                        };
+                        format!(
+                            "{}. {}{}{}{}{}",
+                            index + 1,
+                            match track.url() {
+                                None => title,
+                                Some(url) => format!("[{title}]({url})"),
+                            },
+                            track
+                                .duration
+                                .map(|duration| format!(" `{}`", format_duration(duration)))
+                                .unwrap_or_default(),
+                            track
+                                .published
+                                .and_then(format_date)
+                                .map(|published| format!(" ({published})"))
+                                .unwrap_or_default(),
+                            track
+                                .requester
+                                .map(|requester| format!(" – {}", requester.mention()))
+                                .unwrap_or_default(),
+                            if track.failed_to_load {
+                                " *(nelze přehrát)*"
+                            } else {
+                                ""
+                            }
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                if lines.is_empty() {
+                    lines.push("*fronta je prázdná*".to_owned());
                 }
+                lines.push(String::new());
 
-                queue_text.push_str(
+                if let Some(current_playing_track_index) = queue.current_playing_track_index {
+                    let (remaining_duration, has_unknown_durations) =
+                        remaining_duration(&status, current_playing_track_index);
+                    lines.push(format!(
+                        "*zbývá: {}{}*",
+                        format_duration(remaining_duration),
+                        if has_unknown_durations {
+                            " a položky neznámé délky"
+                        } else {
+                            ""
+                        }
+                    ));
+                }
+                let modes = [
+                    status
+                        .playback_state
+                        .paused
+                        .then_some("pozastaveno"),
+                    status
+                        .playback_state
+                        .repeating
+                        .then_some("opakování položky"),
+                    status.repeating_queue.then_some("opakování fronty"),
                     queue
-                        .tracks
-                        .iter()
-                        .skip(start)
-                        .take(end - start)
-                        .enumerate()
-                        .map(|(index, track)| {
-                            let absolute_index = start + index;
-                            format!(
-                                "{}. [{}]({})",
-                                absolute_index + 1,
-                                match queue.current_playing_track_index {
-                                    Some(current_playing_track_index)
-                                        if absolute_index == current_playing_track_index =>
-                                        format!("**{}** ", track.title),
-                                    None | Some(_) => track.title.clone(),
-                                },
-                                track.youtube_url
-                            )
-                        })
-                        .collect::<Vec<_>>()
-                        .join("\n")
-                        .as_str(),
-                );
-
-                if end != queue.tracks.len() {
-                    queue_text.push_str(
-                        format!("\n*následujících: {}*", queue.tracks.len() - end).as_str(),
-                    );
-                }
-
-                base("Fronta", EmbedIcon::Queue, "Položky ve frontě:").description(queue_text)
+                        .fair
+                        .then_some("střídání podle toho, kdo položky zařadil"),
+                    status.autoplay.then_some("automatické přehrávání"),
+                ]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>();
+                if !modes.is_empty() {
+                    lines.push(format!("*režim: {}*", modes.join(", ")));
+                }
+                lines.push(format!("*strana {} z {page_count}*", page + 1));
+
+                let embed = base("Fronta", EmbedIcon::Queue, "Položky ve frontě:")
+                    .description(lines.join("\n"));
+                // The artwork of the current track, e.g. of a podcast episode.
+                match queue
+                    .current_playing_track_index
+                    .and_then(|index| queue.tracks.get(index))
+                    .and_then(|track| track.thumbnail_url.clone())
+                {
+                    None => embed,
+                    Some(thumbnail_url) => embed.thumbnail(thumbnail_url),
+                }
             }
             command::Executed::QueueMove { index } => base(
                 "Fronta",
                 EmbedIcon::Queue,
                 format!("Přehrávání posunuto na {}. pozici ve frontě.", index + 1),
             ),
+            command::Executed::QueueRemove { indices } => base(
+                "Fronta",
+                EmbedIcon::Queue,
+                if indices.start() == indices.end() {
+                    format!("Z fronty byla odebrána {}. pozice.", indices.start() + 1)
+                } else {
+                    format!(
+                        "Z fronty byly odebrány pozice {}–{}.",
+                        indices.start() + 1,
+                        indices.end() + 1
+                    )
+                },
+            ),
+            command::Executed::QueueRemoveOwn { track_count } => base(
+                "Fronta",
+                EmbedIcon::Queue,
+                match track_count {
+                    0 => "Ve frontě nejsou žádné vámi zařazené položky, které by ještě nezazněly."
+                        .to_owned(),
+                    track_count => {
+                        format!("Z fronty byly odebrány vámi zařazené položky ({track_count}).")
+                    }
+                },
+            ),
+            command::Executed::QueueReorder {
+                from_index,
+                to_index,
+            } => base(
+                "Fronta",
+                EmbedIcon::Queue,
+                format!(
+                    "Položka z {}. pozice byla přesunuta na {}. pozici ve frontě.",
+                    from_index + 1,
+                    to_index + 1
+                ),
+            ),
             command::Executed::QueueRepeat(repeat) => base(
                 "Ovládání",
                 EmbedIcon::Repeat,
@@ -192,6 +713,15 @@ impl From<command::Executed<'_>> for CreateEmbed {
                 )
                 .as_str(),
             ),
+            command::Executed::QueueFair(fair) => base(
+                "Fronta",
+                EmbedIcon::Queue,
+                format!(
+                    "Střídání položek fronty podle toho, kdo je zařadil, je {}.",
+                    if fair { "zapnuto" } else { "vypnuto" }
+                )
+                .as_str(),
+            ),
             command::Executed::QueueShuffle => base(
                 "Ovládání",
                 EmbedIcon::Repeat,
@@ -200,6 +730,15 @@ impl From<command::Executed<'_>> for CreateEmbed {
             command::Executed::Next => {
                 base("Ovládání", EmbedIcon::Next, "Přehrávání další položky.")
             }
+            command::Executed::SkipVote(skip_vote) => base(
+                "Hlasování o přeskočení",
+                EmbedIcon::Next,
+                format!(
+                    "Pro přeskočení hlasovalo {} z potřebných {} posluchačů.",
+                    skip_vote.votes, skip_vote.required_votes
+                )
+                .as_str(),
+            ),
             command::Executed::Previous => base(
                 "Ovládání",
                 EmbedIcon::Previous,
@@ -211,6 +750,16 @@ impl From<command::Executed<'_>> for CreateEmbed {
             command::Executed::Resume => {
                 base("Ovládání", EmbedIcon::Resume, "Přehrávání pokračuje.")
             }
+            command::Executed::Seek(position) => base(
+                "Ovládání",
+                EmbedIcon::Next,
+                format!("Přehrávání posunuto na {}.", format_duration(position)),
+            ),
+            command::Executed::Volume(volume) => base(
+                "Ovládání",
+                EmbedIcon::Volume,
+                format!("Hlasitost nastavena na {volume} %."),
+            ),
             command::Executed::Repeat(repeat) => base(
                 "Ovládání",
                 EmbedIcon::Repeat,
@@ -220,7 +769,83 @@ impl From<command::Executed<'_>> for CreateEmbed {
                 )
                 .as_str(),
             ),
+            command::Executed::Autoplay(autoplay) => base(
+                "Ovládání",
+                EmbedIcon::Next,
+                format!(
+                    "Automatické přehrávání podobných položek po konci fronty je {}.",
+                    if autoplay { "zapnuto" } else { "vypnuto" }
+                )
+                .as_str(),
+            ),
             command::Executed::Stop => base("Ovládání", EmbedIcon::Stop, "Přehrávání zastaveno."),
+            command::Executed::SessionRestore { track_count } => base(
+                "Relace",
+                EmbedIcon::Queue,
+                "Předchozí relace byla obnovena.",
+            )
+            .description(format!("*položek ve frontě: {track_count}*")),
+            command::Executed::VoteSkipSettings {
+                enabled,
+                percentage,
+            } => base(
+                "Nastavení",
+                EmbedIcon::Bot,
+                match enabled {
+                    true => format!(
+                        "Přeskakování položek hlasováním je zapnuto, je třeba {percentage} % posluchačů."
+                    ),
+                    false => "Přeskakování položek hlasováním je vypnuto.".to_owned(),
+                }
+                .as_str(),
+            ),
+            command::Executed::LiveNowPlaying(live_now_playing) => base(
+                "Nastavení",
+                EmbedIcon::Bot,
+                match live_now_playing {
+                    true => "Přehrávání se bude zobrazovat v jediné průběžně upravované zprávě.",
+                    false => "Každá přehrávaná položka bude oznámena novou zprávou.",
+                },
+            ),
+            command::Executed::DjRole(None) => {
+                base("Nastavení", EmbedIcon::Bot, "Role DJ byla zrušena.")
+            }
+            command::Executed::DjRole(Some(role_id)) => {
+                base("Nastavení", EmbedIcon::Bot, "Role DJ byla nastavena.")
+                    .description(format!("*role DJ: {}*", role_id.mention()))
+            }
+            command::Executed::Permission {
+                action_kind,
+                requirement,
+            } => base(
+                "Nastavení",
+                EmbedIcon::Bot,
+                format!("Oprávnění příkazu {} bylo nastaveno.", action_kind.name()).as_str(),
+            )
+            .description(format!(
+                "*příkaz smí použít: {}*",
+                requirement_description(requirement)
+            )),
+            command::Executed::SpotifyMatchForget => base(
+                "Nastavení",
+                EmbedIcon::Bot,
+                "Nalezená položka byla zapomenuta, příště se skladba vyhledá znovu.",
+            ),
+            command::Executed::Limits(limits) => {
+                base("Nastavení", EmbedIcon::Bot, "Limity byly nastaveny.").description(format!(
+                    "*nepřehraných položek ve frontě: {}*\n\
+                    *nepřehraných položek na uživatele: {}*\n\
+                    *délka položky: {}*\n\
+                    *položek z playlistu: {}*",
+                    limit_value_description(limits.max_queue_length),
+                    limit_value_description(limits.max_user_track_count),
+                    limits
+                        .max_track_duration
+                        .map(format_duration)
+                        .unwrap_or_else(|| "bez omezení".to_owned()),
+                    limit_value_description(limits.max_playlist_import_size),
+                ))
+            }
         }
     }
 }
@@ -0,0 +1,4 @@
+load_dotenv::load_dotenv!();
+
+/// Empty disables the local music library.
+pub(crate) const DIRECTORY_PATH: &str = env!("LIBRARY_DIRECTORY_PATH");
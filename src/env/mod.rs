@@ -1,3 +1,6 @@
+pub(crate) mod library;
+pub(crate) mod player;
+pub(crate) mod spotify;
 pub(crate) mod voice;
 
 load_dotenv::load_dotenv!();
@@ -5,3 +8,5 @@ load_dotenv::load_dotenv!();
 pub(crate) const DISCORD_API_TOKEN: &str = env!("DISCORD_API_TOKEN");
 pub(crate) const SPOTIFY_API_CLIENT_ID: &str = env!("SPOTIFY_API_CLIENT_ID");
 pub(crate) const SPOTIFY_API_CLIENT_SECRET: &str = env!("SPOTIFY_API_CLIENT_SECRET");
+/// `automatic`, `button` or `disabled`.
+pub(crate) const SESSION_RESTORE_MODE: &str = env!("SESSION_RESTORE_MODE");
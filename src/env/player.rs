@@ -0,0 +1,9 @@
+load_dotenv::load_dotenv!();
+
+/// In seconds, `0` disables leaving.
+pub(crate) const EMPTY_VOICE_CHANNEL_LEAVE_TIMEOUT: &str =
+    env!("PLAYER_EMPTY_VOICE_CHANNEL_LEAVE_TIMEOUT");
+/// In seconds, `0` disables leaving.
+pub(crate) const IDLE_LEAVE_TIMEOUT: &str = env!("PLAYER_IDLE_LEAVE_TIMEOUT");
+pub(crate) const EMPTY_VOICE_CHANNEL_PAUSE_ENABLED: &str =
+    env!("PLAYER_EMPTY_VOICE_CHANNEL_PAUSE_ENABLED");
@@ -0,0 +1,6 @@
+load_dotenv::load_dotenv!();
+
+/// How many tracks of a Spotify playlist are searched for on YouTube at once.
+pub(crate) const PLAYLIST_SEARCH_CONCURRENCY: &str = env!("SPOTIFY_PLAYLIST_SEARCH_CONCURRENCY");
+/// In seconds, `0` disables the timeout of searching for a single track of a Spotify playlist.
+pub(crate) const PLAYLIST_SEARCH_TIMEOUT: &str = env!("SPOTIFY_PLAYLIST_SEARCH_TIMEOUT");
@@ -0,0 +1,112 @@
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+
+const METADATA_REQUEST_HEADER_NAME: &str = "icy-metadata";
+const METADATA_INTERVAL_HEADER_NAME: &str = "icy-metaint";
+pub(crate) const NAME_HEADER_NAME: &str = "icy-name";
+/// The metadata length is sent in blocks of this many bytes.
+const METADATA_LENGTH_UNIT: usize = 16;
+/// The servers send the metadata every few kilobytes, a larger interval is rejected so that the
+/// buffer of the audio between the metadata stays bounded.
+const MAX_METADATA_INTERVAL: usize = 64 * 1024;
+const STREAM_TITLE_START: &str = "StreamTitle='";
+const STREAM_TITLE_END: &str = "';";
+
+/// The headers asking the server to interleave the metadata into the stream.
+pub(crate) fn metadata_request_headers() -> HeaderMap {
+    HeaderMap::from_iter([(
+        HeaderName::from_static(METADATA_REQUEST_HEADER_NAME),
+        HeaderValue::from_static("1"),
+    )])
+}
+
+/// Returns the count of the audio bytes between the metadata, `None` if the server interleaves no
+/// metadata or the interval is out of bounds.
+pub(crate) fn metadata_interval(headers: &HeaderMap) -> Option<usize> {
+    headers
+        .get(METADATA_INTERVAL_HEADER_NAME)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .filter(|metadata_interval| (1..=MAX_METADATA_INTERVAL).contains(metadata_interval))
+}
+
+/// Reads the titles of the songs announced in the ICY metadata interleaved into Icecast and
+/// Shoutcast streams.
+pub(crate) struct StreamTitles {
+    response: reqwest::Response,
+    metadata_interval: usize,
+    buffer: Vec<u8>,
+    last_title: Option<String>,
+}
+
+impl StreamTitles {
+    /// Returns `None` if the stream does not provide the metadata.
+    pub(crate) async fn connect(
+        http_client: &reqwest::Client,
+        url: &str,
+    ) -> Result<Option<Self>, reqwest::Error> {
+        let response = http_client
+            .get(url)
+            .headers(metadata_request_headers())
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let metadata_interval = match metadata_interval(response.headers()) {
+            None => return Ok(None),
+            Some(metadata_interval) => metadata_interval,
+        };
+
+        Ok(Some(Self {
+            response,
+            metadata_interval,
+            buffer: Vec::new(),
+            last_title: None,
+        }))
+    }
+
+    /// Waits for the title to change, returns `None` once the stream ends.
+    pub(crate) async fn next(&mut self) -> Result<Option<String>, reqwest::Error> {
+        loop {
+            if !self.fill_buffer(self.metadata_interval + 1).await? {
+                return Ok(None);
+            }
+            let metadata_length =
+                usize::from(self.buffer[self.metadata_interval]) * METADATA_LENGTH_UNIT;
+            self.buffer.drain(..=self.metadata_interval);
+            if metadata_length == 0 {
+                continue;
+            }
+
+            if !self.fill_buffer(metadata_length).await? {
+                return Ok(None);
+            }
+            let metadata = String::from_utf8_lossy(&self.buffer[..metadata_length]).into_owned();
+            self.buffer.drain(..metadata_length);
+
+            if let Some(title) = parse_stream_title(&metadata)
+                && self.last_title.as_ref() != Some(&title)
+            {
+                self.last_title = Some(title.clone());
+                return Ok(Some(title));
+            }
+        }
+    }
+
+    /// Returns `false` if the stream ended before the buffer got filled.
+    async fn fill_buffer(&mut self, length: usize) -> Result<bool, reqwest::Error> {
+        while self.buffer.len() < length {
+            match self.response.chunk().await? {
+                None => return Ok(false),
+                Some(chunk) => self.buffer.extend_from_slice(&chunk),
+            }
+        }
+        Ok(true)
+    }
+}
+
+fn parse_stream_title(metadata: &str) -> Option<String> {
+    let start = metadata.find(STREAM_TITLE_START)? + STREAM_TITLE_START.len();
+    let end = start + metadata[start..].find(STREAM_TITLE_END)?;
+
+    Some(metadata[start..end].trim().to_owned()).filter(|title| !title.is_empty())
+}
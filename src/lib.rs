@@ -7,14 +7,20 @@ use songbird::{Config, SerenityInit};
 use unwrap_or_log::LogError;
 
 mod activity;
+mod attachment;
+mod audio;
 mod bot;
 mod command;
 mod embed;
 mod env;
+mod icy;
+mod library;
 mod log;
 mod model;
 mod player;
 mod query;
+mod session;
+mod settings;
 mod utils;
 mod youtube;
 
@@ -0,0 +1,183 @@
+use crate::audio;
+use crate::audio::CoverArt;
+use crate::model::{Source, Track};
+use amplify_derive::Display;
+use deunicode::deunicode;
+use log::{error, info};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use symphonia::core::probe::ProbeResult;
+use thiserror::Error;
+use tokio::task::JoinError;
+
+#[derive(Error, Display, Debug)]
+#[display(Debug)]
+#[allow(dead_code)]
+pub(crate) enum Error {
+    Task(JoinError),
+}
+
+impl From<JoinError> for Error {
+    fn from(join_error: JoinError) -> Self {
+        Self::Task(join_error)
+    }
+}
+
+pub(crate) struct Entry {
+    path: PathBuf,
+    title: String,
+    artist: Option<String>,
+    album: Option<String>,
+    duration: Option<Duration>,
+    /// The normalized words of the title, artist, album and file name.
+    search_words: HashSet<String>,
+    /// The normalized words of the title, all of which a query has to contain.
+    title_words: Vec<String>,
+}
+
+impl From<&Entry> for Track {
+    fn from(entry: &Entry) -> Self {
+        let mut track = Self::new(
+            entry.title.clone(),
+            Source::LocalFile {
+                path: entry.path.clone(),
+            },
+            None,
+            entry.duration,
+        );
+        track.artist = entry.artist.clone();
+        track.album = entry.album.clone();
+        track
+    }
+}
+
+/// An index of the audio files in the local music directory, with their metadata read from the
+/// tags.
+pub(crate) struct Library {
+    entries: Vec<Entry>,
+}
+
+impl Library {
+    /// Walks the directory recursively. Directories which cannot be read and files which cannot be
+    /// read as audio are skipped. An empty path results in an empty library.
+    pub(crate) async fn index(directory_path: impl Into<PathBuf>) -> Result<Self, Error> {
+        let directory_path = directory_path.into();
+        if directory_path.as_os_str().is_empty() {
+            return Ok(Self {
+                entries: Vec::new(),
+            });
+        }
+
+        let entries = tokio::task::spawn_blocking(move || index_directory(&directory_path)).await?;
+        info!("indexed {} files of the local music library", entries.len());
+
+        Ok(Self { entries })
+    }
+
+    /// Returns the entry whose words contain all the whole words of the query, preferring the most
+    /// specific one. The query has to contain the whole title, so that common queries meant for
+    /// the other sources do not match a file just by sharing a word with it.
+    pub(crate) fn search(&self, query: &str) -> Option<&Entry> {
+        let query_words = words(query);
+        if query_words.is_empty() {
+            return None;
+        }
+
+        self.entries
+            .iter()
+            .filter(|entry| {
+                query_words
+                    .iter()
+                    .all(|word| entry.search_words.contains(word))
+                    && entry
+                        .title_words
+                        .iter()
+                        .all(|word| query_words.contains(word))
+            })
+            .min_by_key(|entry| entry.search_words.len())
+    }
+}
+
+/// Splits the text into lowercase ASCII words.
+fn words(text: &str) -> Vec<String> {
+    let mut text = deunicode(text);
+    text.make_ascii_lowercase();
+    text.split(|character: char| !character.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn index_directory(directory_path: &Path) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut directory_paths = vec![directory_path.to_path_buf()];
+
+    while let Some(directory_path) = directory_paths.pop() {
+        let directory_entries = match fs::read_dir(&directory_path) {
+            Err(error) => {
+                error!("{}: {error}", directory_path.display());
+                continue;
+            }
+            Ok(directory_entries) => directory_entries,
+        };
+        for directory_entry in directory_entries {
+            let path = match directory_entry {
+                Err(error) => {
+                    error!("{error}");
+                    continue;
+                }
+                Ok(directory_entry) => directory_entry.path(),
+            };
+
+            if path.is_dir() {
+                directory_paths.push(path);
+            } else if let Some(entry) = read_entry(path) {
+                entries.push(entry);
+            }
+        }
+    }
+
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    entries
+}
+
+fn probe(path: &Path) -> Option<ProbeResult> {
+    audio::probe(
+        Box::new(fs::File::open(path).ok()?),
+        path.extension().and_then(|extension| extension.to_str()),
+    )
+}
+
+fn read_entry(path: PathBuf) -> Option<Entry> {
+    let metadata = audio::read_metadata(&mut probe(&path)?);
+
+    let file_stem = path.file_stem()?.to_string_lossy().into_owned();
+    let search_words = [
+        metadata.title.as_ref(),
+        metadata.artist.as_ref(),
+        metadata.album.as_ref(),
+        Some(&file_stem),
+    ]
+    .into_iter()
+    .flatten()
+    .flat_map(|text| words(text))
+    .collect();
+    let title = metadata.title.unwrap_or(file_stem);
+
+    Some(Entry {
+        path,
+        title_words: words(&title),
+        title,
+        artist: metadata.artist,
+        album: metadata.album,
+        duration: metadata.duration,
+        search_words,
+    })
+}
+
+/// Reads the cover art embedded in the file. Blocks the thread while reading.
+pub(crate) fn read_cover_art(path: &Path) -> Option<CoverArt> {
+    audio::read_cover_art(&mut probe(path)?)
+}
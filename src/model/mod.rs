@@ -1,16 +1,85 @@
-#[derive(Clone, Debug)]
+use serde::{Deserialize, Serialize};
+use serenity::all::UserId;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) enum Source {
+    YouTube {
+        url: String,
+    },
+    /// A file from the local music library.
+    LocalFile {
+        path: PathBuf,
+    },
+    /// A direct link to an audio file or an internet radio stream.
+    HttpStream {
+        url: String,
+        /// Whether the stream announced the titles of its songs in the ICY metadata when probed,
+        /// so that they are watched for only then.
+        #[serde(default)]
+        has_stream_titles: bool,
+    },
+    /// An audio file uploaded to Discord.
+    DiscordAttachment {
+        url: String,
+    },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub(crate) struct Track {
     pub(crate) title: String,
-    pub(crate) youtube_url: String,
+    pub(crate) source: Source,
     pub(crate) thumbnail_url: Option<String>,
+    #[serde(default)]
+    pub(crate) duration: Option<Duration>,
+    #[serde(default)]
+    pub(crate) artist: Option<String>,
+    #[serde(default)]
+    pub(crate) album: Option<String>,
+    /// E.g. of a podcast episode.
+    #[serde(default)]
+    pub(crate) published: Option<SystemTime>,
+    /// The user who enqueued the track, `None` for tracks enqueued by the bot itself.
+    #[serde(default)]
+    pub(crate) requester: Option<UserId>,
+    /// The link the track was resolved from, e.g. a Spotify track found on YouTube.
+    #[serde(default)]
+    pub(crate) original_url: Option<String>,
+    /// Set when the last attempt to play the track failed, such tracks are skipped when advancing
+    /// through the queue.
+    #[serde(default)]
+    pub(crate) failed_to_load: bool,
 }
 
 impl Track {
-    pub(crate) fn new(title: String, youtube_url: String, thumbnail_url: Option<String>) -> Self {
+    pub(crate) fn new(
+        title: String,
+        source: Source,
+        thumbnail_url: Option<String>,
+        duration: Option<Duration>,
+    ) -> Self {
         Self {
             title,
-            youtube_url,
+            source,
             thumbnail_url,
+            duration,
+            artist: None,
+            album: None,
+            published: None,
+            requester: None,
+            original_url: None,
+            failed_to_load: false,
+        }
+    }
+
+    /// A link to the track, if it is available on the web.
+    pub(crate) fn url(&self) -> Option<&str> {
+        match &self.source {
+            Source::YouTube { url }
+            | Source::HttpStream { url, .. }
+            | Source::DiscordAttachment { url } => Some(url),
+            Source::LocalFile { .. } => None,
         }
     }
 }
@@ -1,22 +1,48 @@
+use crate::env::player::{
+    EMPTY_VOICE_CHANNEL_LEAVE_TIMEOUT, EMPTY_VOICE_CHANNEL_PAUSE_ENABLED, IDLE_LEAVE_TIMEOUT,
+};
+use crate::icy::StreamTitles;
+use crate::model::Source;
 pub(crate) use crate::model::Track;
+use crate::session;
+use crate::settings::GuildSettings;
+use crate::youtube;
 use amplify_derive::Display;
+use log::error;
 use rand::SeedableRng;
 use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use serenity::all::{ChannelId, Context, GuildId};
+use serde::{Deserialize, Serialize};
+use serenity::all::{ChannelId, Context, GuildId, MessageId, UserId};
 use serenity::async_trait;
-use songbird::error::JoinError;
+use songbird::error::{ControlError, JoinError, PlayError};
 use songbird::events::context_data::VoiceTick;
-use songbird::input::YoutubeDl;
+use songbird::input::{File, HttpRequest, Input, YoutubeDl};
 use songbird::tracks::{PlayMode, TrackHandle};
 use songbird::{Call, CoreEvent, Event, EventContext, EventHandler, TrackEvent};
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::RangeInclusive;
+use std::sync::{Arc, Weak};
 use thiserror::Error;
 use tokio::sync::Mutex;
-use tokio::time::Duration;
+use tokio::task::AbortHandle;
+use tokio::time::{Duration, sleep};
+use tokio_util::sync::CancellationToken;
 use unwrap_or_log::LogError;
 
 const DISCONNECT_STOP_TIMEOUT_DURATION: Duration = Duration::from_secs(1);
+/// How many of the last tracks of the queue autoplay avoids repeating.
+const AUTOPLAY_HISTORY_LENGTH: usize = 50;
+/// In percent.
+pub(crate) const DEFAULT_VOLUME: u16 = 100;
+/// In percent.
+pub(crate) const MAX_VOLUME: u16 = 200;
+/// How often the pending changes are shown in the live message announcing the current track.
+const NOW_PLAYING_UPDATE_INTERVAL: Duration = Duration::from_secs(2);
+/// How often the live message is updated even without changes, so that its progress bar moves.
+const NOW_PLAYING_REFRESH_INTERVAL: Duration = Duration::from_secs(20);
+/// How many of the upcoming tracks the live message lists.
+const NOW_PLAYING_UPCOMING_TRACK_COUNT: usize = 5;
 
 #[derive(Error, Display, Debug)]
 #[display(Debug)]
@@ -41,6 +67,23 @@ struct OnTrackEndedNotPlayingError;
 #[display(Debug)]
 pub(crate) struct QueueMoveIndexExceedsQueueLengthError(pub(crate) usize);
 
+#[derive(Error, Display, Debug)]
+#[display(Debug)]
+pub(crate) struct QueueRemoveIndexExceedsQueueLengthError(pub(crate) usize);
+
+#[derive(Error, Display, Debug)]
+#[display(Debug)]
+pub(crate) struct QueueReorderIndexExceedsQueueLengthError(pub(crate) usize);
+
+#[derive(Error, Display, Debug)]
+#[display(Debug)]
+pub(crate) enum SeekError {
+    NotPlaying,
+    /// The source does not support seeking, e.g. a live stream.
+    Unsupported,
+    Control(ControlError),
+}
+
 #[derive(Error, Display, Debug)]
 #[display(Debug)]
 pub(crate) struct NoVoiceConnectionError;
@@ -51,15 +94,287 @@ impl From<JoinError> for CreationError {
     }
 }
 
-#[derive(Default, Clone)]
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub(crate) struct Queue {
     pub(crate) tracks: Vec<Track>,
     pub(crate) current_playing_track_index: Option<usize>,
+    /// Whether the upcoming tracks take turns by their requesters. A per-guild setting, so it is not
+    /// a part of the session.
+    #[serde(skip)]
+    pub(crate) fair: bool,
+}
+
+impl Queue {
+    /// Returns the index at which the track has been inserted, clamped to the queue length. The
+    /// current track stays the same.
+    fn insert(&mut self, index: usize, track: Track) -> usize {
+        let index = index.min(self.tracks.len());
+        self.tracks.insert(index, track);
+
+        if let Some(current_playing_track_index) = self.current_playing_track_index
+            && index <= current_playing_track_index
+        {
+            self.current_playing_track_index = Some(current_playing_track_index + 1);
+        }
+
+        index
+    }
+
+    /// Returns whether the current track has been removed, in which case no track is current
+    /// anymore.
+    fn remove(
+        &mut self,
+        indices: RangeInclusive<usize>,
+    ) -> Result<bool, QueueRemoveIndexExceedsQueueLengthError> {
+        if *indices.end() >= self.tracks.len() {
+            Err(QueueRemoveIndexExceedsQueueLengthError(*indices.end()))?;
+        }
+
+        let removed_count = self.tracks.drain(indices.clone()).count();
+
+        Ok(match self.current_playing_track_index {
+            Some(index) if index > *indices.end() => {
+                self.current_playing_track_index = Some(index - removed_count);
+                false
+            }
+            Some(index) if indices.contains(&index) => {
+                self.current_playing_track_index = None;
+                true
+            }
+            _ => false,
+        })
+    }
+
+    /// Moves the track, the current track stays the same.
+    fn reorder(
+        &mut self,
+        from_index: usize,
+        to_index: usize,
+    ) -> Result<(), QueueReorderIndexExceedsQueueLengthError> {
+        for index in [from_index, to_index] {
+            if index >= self.tracks.len() {
+                Err(QueueReorderIndexExceedsQueueLengthError(index))?;
+            }
+        }
+
+        let track = self.tracks.remove(from_index);
+        self.tracks.insert(to_index, track);
+
+        if let Some(index) = self.current_playing_track_index {
+            self.current_playing_track_index = Some(if index == from_index {
+                to_index
+            } else if from_index < index && index <= to_index {
+                index - 1
+            } else if to_index <= index && index < from_index {
+                index + 1
+            } else {
+                index
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Returns the queue without the attachments, whose signed links expire, so that they could
+    /// not be played once restored. An attached current track is succeeded by the following one.
+    fn without_attachments(&self) -> Self {
+        let mut queue = self.clone();
+        for index in (0..queue.tracks.len()).rev() {
+            if matches!(queue.tracks[index].source, Source::DiscordAttachment { .. })
+                && let Ok(true) = queue.remove(index..=index)
+            {
+                queue.current_playing_track_index = Some(index);
+            }
+        }
+        queue
+    }
+
+    /// The index of the first track after the current one. Once nothing is playing, every track
+    /// has already been played, so none of them is upcoming.
+    fn first_upcoming_index(&self) -> usize {
+        self.current_playing_track_index
+            .map_or(self.tracks.len(), |index| index + 1)
+            .min(self.tracks.len())
+    }
+
+    /// Interleaves the upcoming tracks round-robin by their requesters, keeping the order of each
+    /// requester's tracks. The requester of the currently playing track takes their turn last.
+    ///
+    /// Returns the original indices of the tracks in their new order.
+    fn interleave_by_requester(&mut self) -> Vec<usize> {
+        let first_upcoming_index = self.first_upcoming_index();
+        let current_requester = self
+            .current_playing_track_index
+            .and_then(|index| self.tracks.get(index))
+            .map(|track| track.requester);
+
+        let mut tracks_by_requester: Vec<(Option<UserId>, VecDeque<(usize, Track)>)> = Vec::new();
+        for (index, track) in self.tracks.drain(first_upcoming_index..).enumerate() {
+            let index = first_upcoming_index + index;
+            match tracks_by_requester
+                .iter_mut()
+                .find(|(requester, _)| *requester == track.requester)
+            {
+                None => {
+                    tracks_by_requester.push((track.requester, VecDeque::from([(index, track)])))
+                }
+                Some((_, tracks)) => tracks.push_back((index, track)),
+            }
+        }
+        if let Some(position) = tracks_by_requester
+            .iter()
+            .position(|(requester, _)| Some(*requester) == current_requester)
+        {
+            let current_requester_tracks = tracks_by_requester.remove(position);
+            tracks_by_requester.push(current_requester_tracks);
+        }
+
+        let mut original_indices = (0..first_upcoming_index).collect::<Vec<_>>();
+        while !tracks_by_requester.is_empty() {
+            tracks_by_requester.retain_mut(|(_, tracks)| {
+                if let Some((index, track)) = tracks.pop_front() {
+                    original_indices.push(index);
+                    self.tracks.push(track);
+                }
+                !tracks.is_empty()
+            });
+        }
+
+        original_indices
+    }
+}
+
+pub(crate) struct SkipVote {
+    /// Votes from other rounds are not counted.
+    pub(crate) round: u64,
+    pub(crate) votes: usize,
+    pub(crate) required_votes: usize,
+}
+
+/// The state shown by the playback controls.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct PlaybackState {
+    pub(crate) paused: bool,
+    pub(crate) repeating: bool,
+}
+
+/// What the live message announcing the current track shows.
+pub(crate) struct NowPlaying {
+    pub(crate) track: Track,
+    /// The song last announced by the currently playing internet radio.
+    pub(crate) stream_title: Option<String>,
+    pub(crate) position: Duration,
+    pub(crate) playback_state: PlaybackState,
+    pub(crate) repeating_queue: bool,
+    /// Only the first few of them.
+    pub(crate) upcoming_tracks: Vec<Track>,
+    pub(crate) upcoming_track_count: usize,
+}
+
+/// The queue along with the playback modes, as shown to the users.
+pub(crate) struct QueueStatus {
+    pub(crate) queue: Queue,
+    /// Within the current track.
+    pub(crate) position: Duration,
+    pub(crate) playback_state: PlaybackState,
+    pub(crate) repeating_queue: bool,
+    pub(crate) autoplay: bool,
+}
+
+/// Why the live message announcing the current track is updated, ordered by importance, since
+/// the pending updates are merged.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum NowPlayingUpdate {
+    /// Nothing has changed except for the playback position.
+    Refresh,
+    StateChanged,
+    StreamTitleChanged,
+    TrackStarted,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum EnqueuePosition {
+    Back,
+    /// Right after the currently playing track.
+    Next,
+    /// Clamped to the queue length.
+    Index(usize),
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum SeekPosition {
+    Absolute(Duration),
+    Forward(Duration),
+    Backward(Duration),
+}
+
+/// The amounts are in percent.
+#[derive(Clone, Copy)]
+pub(crate) enum VolumeChange {
+    Set(u16),
+    Increase(u16),
+    Decrease(u16),
+}
+
+/// A delayed stop of the player, which is cancelled once its reason passes.
+#[derive(Clone, Copy)]
+enum StopTimer {
+    EmptyVoiceChannel,
+    Idle,
+}
+
+impl StopTimer {
+    const COUNT: usize = 2;
+
+    fn timeout(&self) -> Option<Duration> {
+        match self {
+            StopTimer::EmptyVoiceChannel => EMPTY_VOICE_CHANNEL_LEAVE_TIMEOUT,
+            StopTimer::Idle => IDLE_LEAVE_TIMEOUT,
+        }
+        .parse()
+        .ok()
+        .filter(|seconds| *seconds > 0)
+        .map(Duration::from_secs)
+    }
 }
 
 #[async_trait]
-pub(crate) trait TrackStartedPlayingCallback: Send + Sync + Clone + 'static {
-    async fn on_started_playing(&self, track: Track, channel_id: ChannelId, context: Context);
+pub(crate) trait TrackEventCallback: Send + Sync + Clone + 'static {
+    async fn on_started_playing(
+        &self,
+        track: Track,
+        playback_state: PlaybackState,
+        channel_id: ChannelId,
+        context: Context,
+    );
+
+    async fn on_failed_to_play(
+        &self,
+        track: Track,
+        error: PlayError,
+        channel_id: ChannelId,
+        context: Context,
+    );
+
+    /// Called when an internet radio announces a new song.
+    async fn on_stream_title_changed(
+        &self,
+        track: Track,
+        stream_title: String,
+        channel_id: ChannelId,
+        context: Context,
+    );
+
+    /// Called instead of the other callbacks announcing the playback when a single live message
+    /// is kept, with `None` once nothing is playing. Returns the message to update next time.
+    async fn on_now_playing_changed(
+        &self,
+        now_playing: Option<NowPlaying>,
+        update: NowPlayingUpdate,
+        message_id: Option<MessageId>,
+        channel_id: ChannelId,
+        context: Context,
+    ) -> Option<MessageId>;
 }
 
 #[async_trait]
@@ -67,31 +382,112 @@ pub(crate) trait VoiceTickCallback: Send + Sync + Clone + 'static {
     async fn on_voice_tick(&self, guild_id: GuildId, voice_tick: VoiceTick);
 }
 
-pub(crate) struct Player<S: TrackStartedPlayingCallback, V: VoiceTickCallback> {
+/// An update of the live message, prepared under the lock of the player, so that it is sent without
+/// holding the lock.
+struct NowPlayingMessageUpdate<S: TrackEventCallback> {
+    track_event_callback: S,
+    now_playing: Option<NowPlaying>,
+    update: NowPlayingUpdate,
+    message_id: Option<MessageId>,
+    channel_id: ChannelId,
+    context: Context,
+}
+
+impl<S: TrackEventCallback> NowPlayingMessageUpdate<S> {
+    /// Returns the message to update next time.
+    async fn send(self) -> Option<MessageId> {
+        self.track_event_callback
+            .on_now_playing_changed(
+                self.now_playing,
+                self.update,
+                self.message_id,
+                self.channel_id,
+                self.context,
+            )
+            .await
+    }
+}
+
+/// The per-guild options a player starts with.
+pub(crate) struct Options {
+    /// In percent.
+    pub(crate) volume: u16,
+    pub(crate) fair_queue: bool,
+    pub(crate) live_now_playing: bool,
+}
+
+impl From<&GuildSettings> for Options {
+    fn from(guild_settings: &GuildSettings) -> Self {
+        Self {
+            volume: guild_settings.volume,
+            fair_queue: guild_settings.fair_queue,
+            live_now_playing: guild_settings.live_now_playing,
+        }
+    }
+}
+
+/// What the players share with the rest of the bot.
+pub(crate) struct Services<S: TrackEventCallback> {
+    pub(crate) http_client: reqwest::Client,
+    pub(crate) session_manager: Arc<session::Manager>,
+    pub(crate) recommender: Arc<youtube::Recommender>,
+    pub(crate) track_event_callback: Option<S>,
+}
+
+pub(crate) struct Player<S: TrackEventCallback, V: VoiceTickCallback> {
     http_client: reqwest::Client,
     voice_driver: Arc<Mutex<Call>>,
     track_handle: Option<TrackHandle>,
+    /// Follows the song titles of the currently playing internet radio.
+    stream_title_watcher: Option<AbortHandle>,
     guild_id: GuildId,
+    voice_channel_id: ChannelId,
     text_channel_id: ChannelId,
     context: Context,
     queue: Queue,
     repeating: bool,
     repeating_queue: bool,
+    /// Whether related tracks are enqueued once the queue ends.
+    autoplay: bool,
+    /// In percent.
+    volume: u16,
     is_stopped: bool,
+    /// Cancelled once the player stops, aborting the tasks bound to the session, e.g. the imports.
+    session_cancellation_token: CancellationToken,
+    voice_channel_is_empty: bool,
+    paused_due_to_empty_voice_channel: bool,
+    /// Incremented to cancel the scheduled stops.
+    stop_timer_generations: [u64; StopTimer::COUNT],
+    session_manager: Arc<session::Manager>,
+    recommender: Arc<youtube::Recommender>,
+    weak_self: Weak<Mutex<Self>>,
     // TODO: Make the callbacks accept references instead.
-    track_started_playing_callback: Option<S>,
+    track_event_callback: Option<S>,
     voice_tick_callback: Option<V>,
+    /// Identifies the users speaking in the voice channel, filled as they start speaking.
+    ssrc_user_ids: HashMap<u32, UserId>,
+    /// The users who have voted to skip the current track.
+    skip_votes: HashSet<UserId>,
+    /// Incremented whenever the votes are reset, so that votes for an earlier track are not counted.
+    skip_vote_round: u64,
+    /// Whether a single message announcing the current track is kept up to date, instead of a new
+    /// message being sent for every track.
+    live_now_playing: bool,
+    now_playing_message_id: Option<MessageId>,
+    pending_now_playing_update: Option<NowPlayingUpdate>,
+    now_playing_updater: Option<AbortHandle>,
+    stream_title: Option<String>,
     rng: StdRng,
 }
 
-impl<S: TrackStartedPlayingCallback, V: VoiceTickCallback> Player<S, V> {
+impl<S: TrackEventCallback, V: VoiceTickCallback> Player<S, V> {
     pub(crate) async fn new(
-        http_client: reqwest::Client,
         guild_id: GuildId,
         voice_channel_id: ChannelId,
         text_channel_id: ChannelId,
         context: Context,
-        track_started_playing_callback: Option<S>,
+        options: Options,
+        services: Services<S>,
         voice_tick_callback: Option<V>,
     ) -> Result<Arc<Mutex<Self>>, CreationError> {
         let manager = songbird::get(&context)
@@ -102,62 +498,212 @@ impl<S: TrackStartedPlayingCallback, V: VoiceTickCallback> Player<S, V> {
         let voice_driver = manager.join(guild_id, voice_channel_id).await?;
         voice_driver.lock().await.deafen(true).await?;
 
-        let player = Arc::new(Mutex::new(Self {
-            http_client,
-            voice_driver,
-            track_handle: None,
-            guild_id,
-            text_channel_id,
-            context,
-            queue: Queue::default(),
-            repeating: false,
-            repeating_queue: false,
-            is_stopped: false,
-            track_started_playing_callback,
-            voice_tick_callback,
-            rng: StdRng::from_os_rng(),
-        }));
+        let player = Arc::new_cyclic(|weak_self| {
+            Mutex::new(Self {
+                http_client: services.http_client,
+                voice_driver,
+                track_handle: None,
+                stream_title_watcher: None,
+                guild_id,
+                voice_channel_id,
+                text_channel_id,
+                context,
+                queue: Queue {
+                    fair: options.fair_queue,
+                    ..Queue::default()
+                },
+                repeating: false,
+                repeating_queue: false,
+                autoplay: false,
+                volume: options.volume.min(MAX_VOLUME),
+                is_stopped: false,
+                session_cancellation_token: CancellationToken::new(),
+                voice_channel_is_empty: false,
+                paused_due_to_empty_voice_channel: false,
+                stop_timer_generations: [0; StopTimer::COUNT],
+                session_manager: services.session_manager,
+                recommender: services.recommender,
+                weak_self: weak_self.clone(),
+                track_event_callback: services.track_event_callback,
+                voice_tick_callback,
+                ssrc_user_ids: HashMap::new(),
+                skip_votes: HashSet::new(),
+                skip_vote_round: 0,
+                live_now_playing: false,
+                now_playing_message_id: None,
+                pending_now_playing_update: None,
+                now_playing_updater: None,
+                stream_title: None,
+                rng: StdRng::from_os_rng(),
+            })
+        });
 
         let player_clone = player.clone();
-        let player_clone = player_clone.lock().await;
+        let mut player_clone = player_clone.lock().await;
+        player_clone.schedule_stop(StopTimer::Idle);
+        player_clone.set_live_now_playing(options.live_now_playing);
         let mut voice_driver = player_clone.voice_driver.lock().await;
 
         let voice_driver_event_handler = VoiceDriverEventHandler::new(player.clone());
         voice_driver.add_global_event(TrackEvent::End.into(), voice_driver_event_handler.clone());
+        voice_driver.add_global_event(TrackEvent::Error.into(), voice_driver_event_handler.clone());
         voice_driver.add_global_event(
             CoreEvent::DriverDisconnect.into(),
             voice_driver_event_handler.clone(),
         );
+        voice_driver.add_global_event(
+            CoreEvent::SpeakingStateUpdate.into(),
+            voice_driver_event_handler.clone(),
+        );
         voice_driver.add_global_event(CoreEvent::VoiceTick.into(), voice_driver_event_handler);
 
         Ok(player)
     }
 
-    pub(crate) async fn enqueue(&mut self, track: Track) {
-        self.queue.tracks.push(track.clone());
+    /// Resolves the queue index at which a track enqueued at the given position would be
+    /// inserted.
+    pub(crate) fn enqueue_index(&self, position: EnqueuePosition) -> usize {
+        match position {
+            EnqueuePosition::Back => self.queue.tracks.len(),
+            EnqueuePosition::Next => self
+                .queue
+                .current_playing_track_index
+                .map_or(self.queue.tracks.len(), |index| index + 1),
+            EnqueuePosition::Index(index) => index.min(self.queue.tracks.len()),
+        }
+    }
 
+    /// Returns the index at which the track has been inserted.
+    pub(crate) async fn insert(&mut self, index: usize, track: Track) -> usize {
+        let index = self.queue.insert(index, track);
         if self.queue.current_playing_track_index.is_none() {
-            self.play(self.queue.tracks.len() - 1).await;
+            self.play(index).await;
         }
+        self.request_now_playing_update(NowPlayingUpdate::StateChanged);
+        let index = match self.queue.fair {
+            false => index,
+            true => self
+                .queue
+                .interleave_by_requester()
+                .iter()
+                .position(|original_index| *original_index == index)
+                .unwrap_or(index),
+        };
+        self.save_session().await;
+
+        index
     }
 
     async fn play(&mut self, track_index: usize) {
+        self.cancel_stop(StopTimer::Idle);
+
         let mut driver = self.voice_driver.lock().await;
         driver.stop();
 
         self.queue.current_playing_track_index = Some(track_index);
-        let track = &self.queue.tracks[track_index];
+        self.skip_votes.clear();
+        self.skip_vote_round += 1;
+        let track = &mut self.queue.tracks[track_index];
+        // Gives the track another chance, the cause of the previous failure could have passed.
+        track.failed_to_load = false;
 
-        let youtube_dl = YoutubeDl::new(self.http_client.clone(), track.youtube_url.clone());
-        self.track_handle = Some(driver.play_only_input(youtube_dl.into()));
+        let input: Input = match &track.source {
+            Source::YouTube { url } => YoutubeDl::new(self.http_client.clone(), url.clone()).into(),
+            Source::LocalFile { path } => File::new(path.clone()).into(),
+            Source::HttpStream { url, .. } | Source::DiscordAttachment { url } => {
+                HttpRequest::new(self.http_client.clone(), url.clone()).into()
+            }
+        };
+        self.track_handle = Some(
+            driver.play_only(songbird::tracks::Track::from(input).volume(Self::gain(self.volume))),
+        );
+
+        let track = track.clone();
+        drop(driver);
+        self.save_session().await;
+
+        if let Some(stream_title_watcher) = self.stream_title_watcher.take() {
+            stream_title_watcher.abort();
+        }
+        self.stream_title = None;
+        // Watching opens another download of the stream, which is wasted on the streams without
+        // the titles.
+        if let Source::HttpStream {
+            url,
+            has_stream_titles: true,
+        } = &track.source
+        {
+            self.stream_title_watcher = Some(self.watch_stream_titles(url.clone()));
+        }
 
-        if let Some(track_started_playing_callback) = self.track_started_playing_callback.as_ref() {
-            track_started_playing_callback
-                .on_started_playing(track.clone(), self.text_channel_id, self.context.clone())
+        if self.live_now_playing {
+            self.request_now_playing_update(NowPlayingUpdate::TrackStarted);
+        } else if let Some(track_event_callback) = self.track_event_callback.as_ref() {
+            track_event_callback
+                .on_started_playing(
+                    track,
+                    PlaybackState {
+                        paused: false,
+                        repeating: self.repeating,
+                    },
+                    self.text_channel_id,
+                    self.context.clone(),
+                )
                 .await;
         }
     }
 
+    fn watch_stream_titles(&self, url: String) -> AbortHandle {
+        let http_client = self.http_client.clone();
+        let player = self.weak_self.clone();
+
+        tokio::spawn(async move {
+            let mut stream_titles = match StreamTitles::connect(&http_client, &url).await {
+                Err(error) => {
+                    error!("{error}");
+                    return;
+                }
+                Ok(None) => return,
+                Ok(Some(stream_titles)) => stream_titles,
+            };
+
+            loop {
+                let stream_title = match stream_titles.next().await {
+                    Err(error) => {
+                        error!("{error}");
+                        return;
+                    }
+                    Ok(None) => return,
+                    Ok(Some(stream_title)) => stream_title,
+                };
+
+                let player = match player.upgrade() {
+                    None => return,
+                    Some(player) => player,
+                };
+                let mut player = player.lock().await;
+                let track = match player.queue.current_playing_track_index {
+                    None => return,
+                    Some(index) => player.queue.tracks[index].clone(),
+                };
+                if player.live_now_playing {
+                    player.stream_title = Some(stream_title);
+                    player.request_now_playing_update(NowPlayingUpdate::StreamTitleChanged);
+                } else if let Some(track_event_callback) = player.track_event_callback.as_ref() {
+                    track_event_callback
+                        .on_stream_title_changed(
+                            track,
+                            stream_title,
+                            player.text_channel_id,
+                            player.context.clone(),
+                        )
+                        .await;
+                }
+            }
+        })
+        .abort_handle()
+    }
+
     pub(crate) async fn next(&mut self) -> Result<(), NextNoTrackError> {
         let current_playing_track_index = match self.queue.current_playing_track_index {
             None => Err(NextNoTrackError)?,
@@ -185,8 +731,60 @@ impl<S: TrackStartedPlayingCallback, V: VoiceTickCallback> Player<S, V> {
         Ok(())
     }
 
-    pub(crate) fn queue(&self) -> &Queue {
-        &self.queue
+    /// The tracks after the current one, none if nothing is playing.
+    pub(crate) fn upcoming_tracks(&self) -> &[Track] {
+        &self.queue.tracks[self.queue.first_upcoming_index()..]
+    }
+
+    pub(crate) fn current_track(&self) -> Option<&Track> {
+        self.queue
+            .current_playing_track_index
+            .and_then(|index| self.queue.tracks.get(index))
+    }
+
+    pub(crate) async fn queue_status(&self) -> QueueStatus {
+        QueueStatus {
+            queue: self.queue.clone(),
+            position: self.position().await,
+            playback_state: self.playback_state().await,
+            repeating_queue: self.repeating_queue,
+            autoplay: self.autoplay,
+        }
+    }
+
+    /// Within the current track, zero if nothing is playing.
+    async fn position(&self) -> Duration {
+        match &self.track_handle {
+            None => Duration::ZERO,
+            Some(track_handle) => track_handle
+                .get_info()
+                .await
+                .map_or(Duration::ZERO, |track_state| track_state.position),
+        }
+    }
+
+    pub(crate) fn skip_vote_round(&self) -> u64 {
+        self.skip_vote_round
+    }
+
+    /// Counts only the votes of the given voters, i.e. of those still listening.
+    pub(crate) fn vote_skip(
+        &mut self,
+        user_id: UserId,
+        voter_ids: &[UserId],
+        required_percentage: u8,
+    ) -> SkipVote {
+        self.skip_votes.insert(user_id);
+        SkipVote {
+            round: self.skip_vote_round,
+            votes: voter_ids
+                .iter()
+                .filter(|voter_id| self.skip_votes.contains(voter_id))
+                .count(),
+            required_votes: (voter_ids.len() * usize::from(required_percentage))
+                .div_ceil(100)
+                .max(1),
+        }
     }
 
     pub(crate) async fn queue_move(
@@ -202,8 +800,78 @@ impl<S: TrackStartedPlayingCallback, V: VoiceTickCallback> Player<S, V> {
         Ok(())
     }
 
+    pub(crate) async fn queue_remove(
+        &mut self,
+        indices: RangeInclusive<usize>,
+    ) -> Result<(), QueueRemoveIndexExceedsQueueLengthError> {
+        if self.queue.remove(indices.clone())? {
+            if *indices.start() < self.queue.tracks.len() {
+                self.play(*indices.start()).await;
+            } else if self.repeating_queue && !self.queue.tracks.is_empty() {
+                self.play(0).await;
+            } else {
+                self.voice_driver.lock().await.stop();
+                self.track_handle = None;
+                self.schedule_stop(StopTimer::Idle);
+            }
+        }
+        if self.queue.fair {
+            self.queue.interleave_by_requester();
+        }
+        self.save_session().await;
+        self.request_now_playing_update(NowPlayingUpdate::StateChanged);
+
+        Ok(())
+    }
+
+    /// Removes the upcoming tracks enqueued by the user, returns how many have been removed.
+    pub(crate) async fn queue_remove_requested_by(&mut self, user_id: UserId) -> usize {
+        let first_upcoming_index = self.queue.first_upcoming_index();
+        let track_count = self.queue.tracks.len();
+
+        let mut index = 0;
+        self.queue.tracks.retain(|track| {
+            let keep = index < first_upcoming_index || track.requester != Some(user_id);
+            index += 1;
+            keep
+        });
+
+        let removed_track_count = track_count - self.queue.tracks.len();
+        if removed_track_count > 0 {
+            if self.queue.fair {
+                self.queue.interleave_by_requester();
+            }
+            self.save_session().await;
+            self.request_now_playing_update(NowPlayingUpdate::StateChanged);
+        }
+        removed_track_count
+    }
+
+    pub(crate) async fn queue_reorder(
+        &mut self,
+        from_index: usize,
+        to_index: usize,
+    ) -> Result<(), QueueReorderIndexExceedsQueueLengthError> {
+        self.queue.reorder(from_index, to_index)?;
+        self.save_session().await;
+        self.request_now_playing_update(NowPlayingUpdate::StateChanged);
+
+        Ok(())
+    }
+
     pub(crate) async fn queue_repeat(&mut self, repeat: bool) {
         self.repeating_queue = repeat;
+        self.save_session().await;
+        self.request_now_playing_update(NowPlayingUpdate::StateChanged);
+    }
+
+    pub(crate) async fn set_fair_queue(&mut self, fair: bool) {
+        self.queue.fair = fair;
+        if fair {
+            self.queue.interleave_by_requester();
+            self.save_session().await;
+            self.request_now_playing_update(NowPlayingUpdate::StateChanged);
+        }
     }
 
     pub(crate) async fn queue_shuffle(&mut self) {
@@ -211,31 +879,210 @@ impl<S: TrackStartedPlayingCallback, V: VoiceTickCallback> Player<S, V> {
         self.play(0).await;
     }
 
-    pub(crate) async fn pause(&self) -> songbird::error::TrackResult<()> {
+    pub(crate) async fn pause(&mut self) -> songbird::error::TrackResult<()> {
         if let Some(track_handle) = &self.track_handle {
-            return track_handle.pause();
+            track_handle.pause()?;
+            self.request_now_playing_update(NowPlayingUpdate::StateChanged);
         }
         Ok(())
     }
 
-    pub(crate) async fn resume(&self) -> songbird::error::TrackResult<()> {
+    pub(crate) async fn resume(&mut self) -> songbird::error::TrackResult<()> {
         if let Some(track_handle) = &self.track_handle {
-            return track_handle.play();
+            track_handle.play()?;
+            self.request_now_playing_update(NowPlayingUpdate::StateChanged);
         }
         Ok(())
     }
 
+    pub(crate) async fn playback_state(&self) -> PlaybackState {
+        PlaybackState {
+            paused: match &self.track_handle {
+                None => false,
+                Some(track_handle) => track_handle
+                    .get_info()
+                    .await
+                    .is_ok_and(|track_state| matches!(track_state.playing, PlayMode::Pause)),
+            },
+            repeating: self.repeating,
+        }
+    }
+
+    /// Returns the position the playback has actually been moved to.
+    pub(crate) async fn seek(&mut self, position: SeekPosition) -> Result<Duration, SeekError> {
+        let track_handle = self.track_handle.clone().ok_or(SeekError::NotPlaying)?;
+
+        let current_position = match track_handle.get_info().await {
+            Ok(track_state) => track_state.position,
+            Err(ControlError::Finished) => Err(SeekError::NotPlaying)?,
+            Err(error) => Err(SeekError::Control(error))?,
+        };
+        let position = match position {
+            SeekPosition::Absolute(position) => position,
+            SeekPosition::Forward(offset) => current_position.saturating_add(offset),
+            SeekPosition::Backward(offset) => current_position.saturating_sub(offset),
+        };
+        let position = match self.current_track().and_then(|track| track.duration) {
+            None => position,
+            Some(duration) => position.min(duration),
+        };
+
+        match track_handle.seek_async(position).await {
+            Ok(position) => {
+                self.request_now_playing_update(NowPlayingUpdate::StateChanged);
+                Ok(position)
+            }
+            Err(ControlError::Play(PlayError::Seek(_))) => Err(SeekError::Unsupported),
+            Err(ControlError::Finished) => Err(SeekError::NotPlaying),
+            Err(error) => Err(SeekError::Control(error)),
+        }
+    }
+
+    /// Applies to the current track as well as all the following ones. Returns the resulting
+    /// volume.
+    pub(crate) async fn change_volume(
+        &mut self,
+        volume_change: VolumeChange,
+    ) -> songbird::error::TrackResult<u16> {
+        self.volume = match volume_change {
+            VolumeChange::Set(volume) => volume,
+            VolumeChange::Increase(step) => self.volume.saturating_add(step),
+            VolumeChange::Decrease(step) => self.volume.saturating_sub(step),
+        }
+        .min(MAX_VOLUME);
+
+        if let Some(track_handle) = &self.track_handle {
+            match track_handle.set_volume(Self::gain(self.volume)) {
+                Ok(()) | Err(ControlError::Finished) => (),
+                Err(error) => Err(error)?,
+            }
+        }
+
+        Ok(self.volume)
+    }
+
+    fn gain(volume: u16) -> f32 {
+        f32::from(volume) / 100.0
+    }
+
     pub(crate) async fn repeat(&mut self, repeat: bool) {
         self.repeating = repeat;
+        self.save_session().await;
+        self.request_now_playing_update(NowPlayingUpdate::StateChanged);
+    }
+
+    pub(crate) async fn set_autoplay(&mut self, autoplay: bool) {
+        self.autoplay = autoplay;
+        self.save_session().await;
+
+        if autoplay && self.queue.current_playing_track_index.is_none() {
+            self.autoplay_next();
+        }
+    }
+
+    /// Enqueues a track related to the last one in the queue, unless the player has started
+    /// playing something else in the meantime.
+    fn autoplay_next(&self) {
+        let seed_track = match self.queue.tracks.last() {
+            None => return,
+            Some(track) => track.clone(),
+        };
+        let excluded_urls = self
+            .queue
+            .tracks
+            .iter()
+            .rev()
+            .take(AUTOPLAY_HISTORY_LENGTH)
+            .filter_map(|track| track.url().map(str::to_owned))
+            .collect::<Vec<_>>();
+        let recommender = self.recommender.clone();
+        let player = self.weak_self.clone();
+
+        tokio::spawn(async move {
+            let excluded_urls = excluded_urls.iter().map(String::as_str).collect::<Vec<_>>();
+            let track = match recommender.recommend(&seed_track, &excluded_urls).await {
+                Err(error) => {
+                    error!("{error}");
+                    return;
+                }
+                Ok(None) => return,
+                Ok(Some(track)) => track,
+            };
+
+            let player = match player.upgrade() {
+                None => return,
+                Some(player) => player,
+            };
+            let mut player = player.lock().await;
+            if !player.is_stopped
+                && player.autoplay
+                && player.queue.current_playing_track_index.is_none()
+            {
+                let index = player.enqueue_index(EnqueuePosition::Back);
+                player.insert(index, track).await;
+            }
+        });
     }
 
     pub(crate) async fn stop(&mut self) {
         self.is_stopped = true;
+        self.session_cancellation_token.cancel();
+
+        if let Some(stream_title_watcher) = self.stream_title_watcher.take() {
+            stream_title_watcher.abort();
+        }
+        if let Some(now_playing_updater) = self.now_playing_updater.take() {
+            now_playing_updater.abort();
+            // The final state is shown right away, since the updater does not run anymore.
+            if let Some(message_update) = self
+                .prepare_now_playing_message_update(NowPlayingUpdate::StateChanged)
+                .await
+            {
+                tokio::spawn(message_update.send());
+            }
+        }
 
         let mut voice_driver = self.voice_driver.lock().await;
         voice_driver.stop();
         _ = voice_driver.leave().await;
         voice_driver.remove_all_global_events();
+
+        self.session_manager.remove(self.guild_id).await;
+    }
+
+    /// Resumes the playback of a session saved before the bot restarted.
+    pub(crate) async fn restore_session(&mut self, session: session::Session) {
+        self.queue = Queue {
+            fair: self.queue.fair,
+            ..session.queue
+        };
+        self.repeating = session.repeating;
+        self.repeating_queue = session.repeating_queue;
+        self.autoplay = session.autoplay;
+
+        match self.queue.current_playing_track_index {
+            Some(index) if index < self.queue.tracks.len() => self.play(index).await,
+            _ => {
+                self.queue.current_playing_track_index = None;
+                self.save_session().await;
+            }
+        }
+    }
+
+    async fn save_session(&self) {
+        self.session_manager
+            .save(
+                self.guild_id,
+                session::Session {
+                    queue: self.queue.without_attachments(),
+                    repeating: self.repeating,
+                    repeating_queue: self.repeating_queue,
+                    autoplay: self.autoplay,
+                    voice_channel_id: self.voice_channel_id,
+                    text_channel_id: self.text_channel_id,
+                },
+            )
+            .await;
     }
 
     async fn on_track_ended(&mut self) -> Result<(), OnTrackEndedNotPlayingError> {
@@ -249,15 +1096,135 @@ impl<S: TrackStartedPlayingCallback, V: VoiceTickCallback> Player<S, V> {
             return Ok(());
         }
 
-        if let Err(NextNoTrackError) = self.next().await {
-            if self.repeating_queue {
-                self.play(0).await;
-            } else {
+        self.play_following().await;
+        Ok(())
+    }
+
+    /// Skips the track if it is the one currently playing. Failed seeks are handled by
+    /// [`Self::seek`] instead.
+    async fn on_track_failed(&mut self, track_handle: &TrackHandle, error: &PlayError) {
+        if matches!(error, PlayError::Seek(_))
+            || self
+                .track_handle
+                .as_ref()
+                .is_none_or(|current_track_handle| {
+                    current_track_handle.uuid() != track_handle.uuid()
+                })
+        {
+            return;
+        }
+        let track = match self.queue.current_playing_track_index {
+            None => return,
+            Some(index) => &mut self.queue.tracks[index],
+        };
+        track.failed_to_load = true;
+        let track = track.clone();
+
+        if let Some(track_event_callback) = self.track_event_callback.as_ref() {
+            track_event_callback
+                .on_failed_to_play(
+                    track,
+                    error.clone(),
+                    self.text_channel_id,
+                    self.context.clone(),
+                )
+                .await;
+        }
+
+        self.play_following().await;
+    }
+
+    /// Plays the first following track which has not failed to load, wrapping around if the queue
+    /// is repeating.
+    async fn play_following(&mut self) {
+        let following_track_index =
+            self.queue
+                .current_playing_track_index
+                .and_then(|current_playing_track_index| {
+                    let wrapped_end = if self.repeating_queue {
+                        current_playing_track_index + 1
+                    } else {
+                        0
+                    };
+                    (current_playing_track_index + 1..self.queue.tracks.len())
+                        .chain(0..wrapped_end)
+                        .find(|index| !self.queue.tracks[*index].failed_to_load)
+                });
+
+        match following_track_index {
+            Some(index) => self.play(index).await,
+            None => {
                 self.queue.current_playing_track_index = None;
+                self.save_session().await;
+                self.request_now_playing_update(NowPlayingUpdate::StateChanged);
+                self.schedule_stop(StopTimer::Idle);
+
+                if self.autoplay {
+                    self.autoplay_next();
+                }
             }
         }
+    }
 
-        Ok(())
+    /// Leaves after a timeout once no user is left in the voice channel, and optionally pauses the
+    /// playback until someone joins again.
+    pub(crate) async fn set_voice_channel_is_empty(&mut self, is_empty: bool) {
+        if self.voice_channel_is_empty == is_empty {
+            return;
+        }
+        self.voice_channel_is_empty = is_empty;
+
+        if is_empty {
+            self.schedule_stop(StopTimer::EmptyVoiceChannel);
+
+            if EMPTY_VOICE_CHANNEL_PAUSE_ENABLED == "true"
+                && let Some(track_handle) = &self.track_handle
+                && track_handle
+                    .get_info()
+                    .await
+                    .is_ok_and(|track_state| matches!(track_state.playing, PlayMode::Play))
+            {
+                self.paused_due_to_empty_voice_channel = track_handle.pause().log_error().is_ok();
+                self.request_now_playing_update(NowPlayingUpdate::StateChanged);
+            }
+        } else {
+            self.cancel_stop(StopTimer::EmptyVoiceChannel);
+
+            if self.paused_due_to_empty_voice_channel {
+                self.paused_due_to_empty_voice_channel = false;
+                _ = self.resume().await.log_error();
+            }
+        }
+    }
+
+    fn schedule_stop(&mut self, stop_timer: StopTimer) {
+        self.cancel_stop(stop_timer);
+
+        let timeout = match stop_timer.timeout() {
+            None => return,
+            Some(timeout) => timeout,
+        };
+        let generation = self.stop_timer_generations[stop_timer as usize];
+        let player = self.weak_self.clone();
+
+        tokio::spawn(async move {
+            sleep(timeout).await;
+
+            let player = match player.upgrade() {
+                None => return,
+                Some(player) => player,
+            };
+            let mut player = player.lock().await;
+            if !player.is_stopped
+                && player.stop_timer_generations[stop_timer as usize] == generation
+            {
+                player.stop().await;
+            }
+        });
+    }
+
+    fn cancel_stop(&mut self, stop_timer: StopTimer) {
+        self.stop_timer_generations[stop_timer as usize] += 1;
     }
 
     async fn on_disconnected(&mut self) {
@@ -274,6 +1241,107 @@ impl<S: TrackStartedPlayingCallback, V: VoiceTickCallback> Player<S, V> {
         }
     }
 
+    /// Starts or stops keeping the single live message, whose updates are applied periodically.
+    pub(crate) fn set_live_now_playing(&mut self, live_now_playing: bool) {
+        self.live_now_playing = live_now_playing;
+        if let Some(now_playing_updater) = self.now_playing_updater.take() {
+            now_playing_updater.abort();
+        }
+        if !live_now_playing || self.is_stopped {
+            return;
+        }
+
+        self.request_now_playing_update(NowPlayingUpdate::TrackStarted);
+        let player = self.weak_self.clone();
+        self.now_playing_updater = Some(
+            tokio::spawn(async move {
+                let mut since_last_update = Duration::ZERO;
+                loop {
+                    sleep(NOW_PLAYING_UPDATE_INTERVAL).await;
+                    since_last_update += NOW_PLAYING_UPDATE_INTERVAL;
+
+                    let player = match player.upgrade() {
+                        None => return,
+                        Some(player) => player,
+                    };
+                    let message_update = {
+                        let mut player = player.lock().await;
+                        let update = match player.pending_now_playing_update.take() {
+                            Some(update) => update,
+                            None if since_last_update >= NOW_PLAYING_REFRESH_INTERVAL
+                                && player.queue.current_playing_track_index.is_some() =>
+                            {
+                                NowPlayingUpdate::Refresh
+                            }
+                            None => continue,
+                        };
+                        match player.prepare_now_playing_message_update(update).await {
+                            None => continue,
+                            Some(message_update) => message_update,
+                        }
+                    };
+                    let message_id = message_update.send().await;
+                    player.lock().await.now_playing_message_id = message_id;
+                    since_last_update = Duration::ZERO;
+                }
+            })
+            .abort_handle(),
+        );
+    }
+
+    /// The live message, if any.
+    pub(crate) fn now_playing_message_id(&self) -> Option<MessageId> {
+        self.now_playing_message_id
+    }
+
+    /// Merges the update with the pending one, so that bursts of changes, e.g. enqueueing a
+    /// playlist, result in a single edit of the live message.
+    fn request_now_playing_update(&mut self, update: NowPlayingUpdate) {
+        if self.live_now_playing {
+            self.pending_now_playing_update = self.pending_now_playing_update.max(Some(update));
+        }
+    }
+
+    async fn prepare_now_playing_message_update(
+        &self,
+        update: NowPlayingUpdate,
+    ) -> Option<NowPlayingMessageUpdate<S>> {
+        Some(NowPlayingMessageUpdate {
+            track_event_callback: self.track_event_callback.clone()?,
+            now_playing: match self.is_stopped {
+                false => self.now_playing().await,
+                true => None,
+            },
+            update,
+            message_id: self.now_playing_message_id,
+            channel_id: self.text_channel_id,
+            context: self.context.clone(),
+        })
+    }
+
+    async fn now_playing(&self) -> Option<NowPlaying> {
+        let track = self.current_track()?.clone();
+        let upcoming_tracks = self.upcoming_tracks();
+
+        Some(NowPlaying {
+            track,
+            stream_title: self.stream_title.clone(),
+            position: self.position().await,
+            playback_state: self.playback_state().await,
+            repeating_queue: self.repeating_queue,
+            upcoming_tracks: upcoming_tracks
+                .iter()
+                .take(NOW_PLAYING_UPCOMING_TRACK_COUNT)
+                .cloned()
+                .collect(),
+            upcoming_track_count: upcoming_tracks.len(),
+        })
+    }
+
+    pub(crate) fn user_id_by_ssrc(&self, ssrc: u32) -> Option<UserId> {
+        self.ssrc_user_ids.get(&ssrc).copied()
+    }
+
     async fn on_voice_tick(&self, voice_tick: VoiceTick) {
         if let Some(voice_tick_callback) = self.voice_tick_callback.as_ref() {
             voice_tick_callback
@@ -301,40 +1369,58 @@ impl<S: TrackStartedPlayingCallback, V: VoiceTickCallback> Player<S, V> {
         self.text_channel_id
     }
 
-    pub(crate) fn set_text_channel_id(&mut self, channel_id: ChannelId) {
-        self.text_channel_id = channel_id
+    pub(crate) async fn set_text_channel_id(&mut self, channel_id: ChannelId) {
+        if self.text_channel_id != channel_id {
+            self.text_channel_id = channel_id;
+            self.save_session().await;
+        }
     }
 
     pub(crate) fn is_stopped(&self) -> bool {
         self.is_stopped
     }
+
+    /// Whether the order of the upcoming tracks is given by their requesters taking turns.
+    pub(crate) fn is_queue_fair(&self) -> bool {
+        self.queue.fair
+    }
+
+    pub(crate) fn session_cancellation_token(&self) -> &CancellationToken {
+        &self.session_cancellation_token
+    }
 }
 
 #[derive(Clone)]
-struct VoiceDriverEventHandler<S: TrackStartedPlayingCallback, V: VoiceTickCallback> {
+struct VoiceDriverEventHandler<S: TrackEventCallback, V: VoiceTickCallback> {
     player: Arc<Mutex<Player<S, V>>>,
 }
 
-impl<S: TrackStartedPlayingCallback, V: VoiceTickCallback> VoiceDriverEventHandler<S, V> {
+impl<S: TrackEventCallback, V: VoiceTickCallback> VoiceDriverEventHandler<S, V> {
     pub(crate) fn new(player: Arc<Mutex<Player<S, V>>>) -> Self {
         Self { player }
     }
 }
 
 #[async_trait]
-impl<S: TrackStartedPlayingCallback, V: VoiceTickCallback> EventHandler
-    for VoiceDriverEventHandler<S, V>
-{
+impl<S: TrackEventCallback, V: VoiceTickCallback> EventHandler for VoiceDriverEventHandler<S, V> {
     async fn act(&self, context: &EventContext<'_>) -> Option<Event> {
         match context {
-            EventContext::Track([(track_state, _)]) => {
-                if let PlayMode::End = track_state.playing {
+            EventContext::Track([(track_state, track_handle)]) => match &track_state.playing {
+                PlayMode::End => {
                     let mut player = self.player.lock().await;
                     if player.on_track_ended().await.log_error().is_err() {
                         player.stop().await
                     }
                 }
-            }
+                PlayMode::Errored(error) => {
+                    self.player
+                        .lock()
+                        .await
+                        .on_track_failed(track_handle, error)
+                        .await;
+                }
+                _ => (),
+            },
             EventContext::VoiceTick(voice_tick) => {
                 self.player
                     .lock()
@@ -342,9 +1428,202 @@ impl<S: TrackStartedPlayingCallback, V: VoiceTickCallback> EventHandler
                     .on_voice_tick(voice_tick.clone())
                     .await;
             }
+            EventContext::SpeakingStateUpdate(speaking) => {
+                if let Some(user_id) = speaking.user_id {
+                    self.player
+                        .lock()
+                        .await
+                        .ssrc_user_ids
+                        .insert(speaking.ssrc, UserId::new(user_id.0));
+                }
+            }
             EventContext::DriverDisconnect(_) => self.player.lock().await.on_disconnected().await,
             _ => (),
         }
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(title: &str, requester: Option<u64>) -> Track {
+        let mut track = Track::new(
+            title.to_owned(),
+            Source::YouTube {
+                url: format!("https://www.youtube.com/watch?v={title}"),
+            },
+            None,
+            None,
+        );
+        track.requester = requester.map(UserId::new);
+        track
+    }
+
+    fn queue(titles: &[&str], current_playing_track_index: Option<usize>) -> Queue {
+        Queue {
+            tracks: titles.iter().map(|title| track(title, None)).collect(),
+            current_playing_track_index,
+            fair: false,
+        }
+    }
+
+    fn titles(queue: &Queue) -> Vec<&str> {
+        queue
+            .tracks
+            .iter()
+            .map(|track| track.title.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn insert_before_current_shifts_current() {
+        let mut queue = queue(&["a", "b", "c"], Some(1));
+        assert_eq!(queue.insert(0, track("x", None)), 0);
+        assert_eq!(titles(&queue), ["x", "a", "b", "c"]);
+        assert_eq!(queue.current_playing_track_index, Some(2));
+    }
+
+    #[test]
+    fn insert_at_current_shifts_current() {
+        let mut queue = queue(&["a", "b", "c"], Some(1));
+        assert_eq!(queue.insert(1, track("x", None)), 1);
+        assert_eq!(titles(&queue), ["a", "x", "b", "c"]);
+        assert_eq!(queue.current_playing_track_index, Some(2));
+    }
+
+    #[test]
+    fn insert_after_current_keeps_current() {
+        let mut queue = queue(&["a", "b", "c"], Some(1));
+        assert_eq!(queue.insert(2, track("x", None)), 2);
+        assert_eq!(titles(&queue), ["a", "b", "x", "c"]);
+        assert_eq!(queue.current_playing_track_index, Some(1));
+    }
+
+    #[test]
+    fn insert_past_end_is_clamped() {
+        let mut queue = queue(&["a", "b"], None);
+        assert_eq!(queue.insert(10, track("x", None)), 2);
+        assert_eq!(titles(&queue), ["a", "b", "x"]);
+        assert_eq!(queue.current_playing_track_index, None);
+    }
+
+    #[test]
+    fn remove_before_current_shifts_current() {
+        let mut queue = queue(&["a", "b", "c", "d"], Some(2));
+        assert!(!queue.remove(0..=1).unwrap());
+        assert_eq!(titles(&queue), ["c", "d"]);
+        assert_eq!(queue.current_playing_track_index, Some(0));
+    }
+
+    #[test]
+    fn remove_at_current_clears_current() {
+        let mut queue = queue(&["a", "b", "c", "d"], Some(2));
+        assert!(queue.remove(1..=2).unwrap());
+        assert_eq!(titles(&queue), ["a", "d"]);
+        assert_eq!(queue.current_playing_track_index, None);
+    }
+
+    #[test]
+    fn remove_after_current_keeps_current() {
+        let mut queue = queue(&["a", "b", "c", "d"], Some(1));
+        assert!(!queue.remove(2..=3).unwrap());
+        assert_eq!(titles(&queue), ["a", "b"]);
+        assert_eq!(queue.current_playing_track_index, Some(1));
+    }
+
+    #[test]
+    fn remove_past_end_fails_without_changes() {
+        let mut queue = queue(&["a", "b", "c"], Some(0));
+        assert!(queue.remove(1..=3).is_err());
+        assert_eq!(titles(&queue), ["a", "b", "c"]);
+        assert_eq!(queue.current_playing_track_index, Some(0));
+    }
+
+    #[test]
+    fn reorder_from_before_to_after_current() {
+        let mut queue = queue(&["a", "b", "c", "d"], Some(1));
+        queue.reorder(0, 3).unwrap();
+        assert_eq!(titles(&queue), ["b", "c", "d", "a"]);
+        assert_eq!(queue.current_playing_track_index, Some(0));
+    }
+
+    #[test]
+    fn reorder_from_after_to_before_current() {
+        let mut queue = queue(&["a", "b", "c", "d"], Some(1));
+        queue.reorder(3, 0).unwrap();
+        assert_eq!(titles(&queue), ["d", "a", "b", "c"]);
+        assert_eq!(queue.current_playing_track_index, Some(2));
+    }
+
+    #[test]
+    fn reorder_current_follows_it() {
+        let mut queue = queue(&["a", "b", "c", "d"], Some(1));
+        queue.reorder(1, 3).unwrap();
+        assert_eq!(titles(&queue), ["a", "c", "d", "b"]);
+        assert_eq!(queue.current_playing_track_index, Some(3));
+    }
+
+    #[test]
+    fn reorder_past_end_fails() {
+        let mut queue = queue(&["a", "b"], Some(0));
+        assert!(queue.reorder(0, 2).is_err());
+        assert_eq!(titles(&queue), ["a", "b"]);
+    }
+
+    #[test]
+    fn without_attachments_succeeds_attached_current_by_following() {
+        let mut queue = queue(&["a", "b", "c", "d"], Some(2));
+        for index in [1, 2] {
+            queue.tracks[index].source = Source::DiscordAttachment {
+                url: format!("https://cdn.discordapp.com/attachments/{index}.mp3"),
+            };
+        }
+
+        let queue = queue.without_attachments();
+
+        assert_eq!(titles(&queue), ["a", "d"]);
+        assert_eq!(queue.current_playing_track_index, Some(1));
+    }
+
+    #[test]
+    fn interleave_puts_current_requester_last() {
+        let mut queue = Queue {
+            tracks: vec![
+                track("played", Some(1)),
+                track("current", Some(1)),
+                track("a1", Some(1)),
+                track("a2", Some(1)),
+                track("b1", Some(2)),
+                track("b2", Some(2)),
+                track("c1", Some(3)),
+            ],
+            current_playing_track_index: Some(1),
+            fair: true,
+        };
+        let original_indices = queue.interleave_by_requester();
+        assert_eq!(
+            titles(&queue),
+            ["played", "current", "b1", "c1", "a1", "b2", "a2"]
+        );
+        assert_eq!(original_indices, [0, 1, 4, 6, 2, 5, 3]);
+        assert_eq!(queue.current_playing_track_index, Some(1));
+    }
+
+    #[test]
+    fn interleave_without_current_keeps_played_tracks() {
+        let mut queue = Queue {
+            tracks: vec![
+                track("a1", Some(1)),
+                track("a2", Some(1)),
+                track("b1", Some(2)),
+            ],
+            current_playing_track_index: None,
+            fair: true,
+        };
+        let original_indices = queue.interleave_by_requester();
+        assert_eq!(titles(&queue), ["a1", "a2", "b1"]);
+        assert_eq!(original_indices, [0, 1, 2]);
+    }
+}
@@ -0,0 +1,246 @@
+use crate::model::{Source, Track};
+use crate::query::{Fetched, FetchedSingleTrack};
+use crate::{icy, query};
+use log::error;
+use reqwest::header::{CONTENT_TYPE, HeaderMap, LOCATION};
+use reqwest::redirect::Policy;
+use reqwest::{Response, Url};
+use serenity::async_trait;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::net::lookup_host;
+
+/// The hosts whose links are either handled by the other fetchers, or are web pages, so that no
+/// request is wasted on them.
+const SKIPPED_HOSTS: [&str; 4] = ["youtube.com", "youtu.be", "spotify.com", "spotify.link"];
+
+/// Returns the query as a URL if it is an HTTP one which is not on any of the skipped hosts.
+pub(crate) fn parse_url(query: &str) -> Option<Url> {
+    let url = Url::parse(query.trim()).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    let host = url.host_str()?;
+    let is_skipped = SKIPPED_HOSTS.iter().any(|skipped_host| {
+        host.strip_suffix(skipped_host)
+            .is_some_and(|subdomain| subdomain.is_empty() || subdomain.ends_with('.'))
+    });
+    (!is_skipped).then_some(url)
+}
+
+/// How many redirects of a link given by a user are followed.
+const MAX_REDIRECT_COUNT: usize = 5;
+
+/// Creates the client requesting the links given by the users. It follows no redirects on its own,
+/// so that [`get_public`] can check each of them.
+pub(crate) fn create_probing_client() -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder().redirect(Policy::none()).build()
+}
+
+/// Follows the redirects, checking that every URL leads to a public address, so that the users
+/// cannot make the bot request the services of its own host or network. Returns the final URL
+/// along with its response, `None` if any of the URLs does not lead to a public address or there
+/// are too many redirects.
+pub(crate) async fn get_public(
+    http_client: &reqwest::Client,
+    mut url: Url,
+    headers: HeaderMap,
+    timeout: Duration,
+) -> reqwest::Result<Option<(Url, Response)>> {
+    for _ in 0..=MAX_REDIRECT_COUNT {
+        if !matches!(url.scheme(), "http" | "https") || !is_public(&url).await {
+            return Ok(None);
+        }
+
+        let response = http_client
+            .get(url.clone())
+            .headers(headers.clone())
+            .timeout(timeout)
+            .send()
+            .await?;
+        let location = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .and_then(|location| url.join(location).ok());
+        match location {
+            Some(location) if response.status().is_redirection() => url = location,
+            _ => return Ok(Some((url, response))),
+        }
+    }
+
+    Ok(None)
+}
+
+/// Whether all the addresses the host of the URL resolves to are public.
+async fn is_public(url: &Url) -> bool {
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    // The IPv6 addresses are enclosed in brackets in the URLs.
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    // The IP addresses are parsed without any lookup.
+    let addresses = match lookup_host((host, url.port_or_known_default().unwrap_or(80))).await {
+        Err(_) => return false,
+        Ok(socket_addresses) => socket_addresses
+            .map(|socket_address| socket_address.ip())
+            .collect::<Vec<_>>(),
+    };
+
+    !addresses.is_empty() && addresses.into_iter().all(is_public_address)
+}
+
+fn is_public_address(address: IpAddr) -> bool {
+    match address {
+        IpAddr::V4(address) => {
+            let [first_octet, second_octet, ..] = address.octets();
+            // The shared address space of the carrier-grade NATs, 100.64.0.0/10.
+            let is_shared = first_octet == 100 && (64..128).contains(&second_octet);
+            !(address.is_loopback()
+                || address.is_private()
+                || address.is_link_local()
+                || address.is_unspecified()
+                || address.is_broadcast()
+                || address.is_documentation()
+                || is_shared)
+        }
+        IpAddr::V6(address) => match address.to_ipv4_mapped() {
+            Some(address) => is_public_address(IpAddr::V4(address)),
+            None => {
+                !(address.is_loopback()
+                    || address.is_unspecified()
+                    || address.is_unique_local()
+                    || address.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+/// Recognizes direct links to audio files and internet radio streams.
+pub(crate) struct Fetcher {
+    http_client: reqwest::Client,
+}
+
+impl Fetcher {
+    const AUDIO_FILE_EXTENSIONS: [&'static str; 9] = [
+        "aac", "flac", "m4a", "mp3", "oga", "ogg", "opus", "wav", "weba",
+    ];
+    const AUDIO_CONTENT_TYPES: [&'static str; 2] = ["audio/", "application/ogg"];
+    /// Bounds the wait for the response headers, so that an unresponsive server does not hold up
+    /// the other fetchers.
+    const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+    pub(crate) fn new(http_client: reqwest::Client) -> Self {
+        Self { http_client }
+    }
+}
+
+#[async_trait]
+impl query::Fetcher for Fetcher {
+    async fn fetch<'a>(&'a self, query: &'a str) -> anyhow::Result<Option<Fetched<'a>>> {
+        let url = match parse_url(query) {
+            None => return Ok(None),
+            Some(url) => url,
+        };
+        let file_name = url
+            .path_segments()
+            .and_then(|mut path_segments| path_segments.next_back())
+            .filter(|file_name| !file_name.is_empty())
+            .map(str::to_owned);
+        let has_audio_file_extension = file_name
+            .as_ref()
+            .and_then(|file_name| file_name.rsplit_once('.'))
+            .is_some_and(|(_, extension)| {
+                Self::AUDIO_FILE_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+            });
+
+        // Only the headers are read, dropping the response closes the connection. A failed request
+        // only means the query is not a link to audio, the other fetchers may still recognize it.
+        let (url, response) = match get_public(
+            &self.http_client,
+            url,
+            icy::metadata_request_headers(),
+            Self::RESPONSE_TIMEOUT,
+        )
+        .await
+        {
+            Err(error) => {
+                error!("{error}");
+                return Ok(None);
+            }
+            Ok(None) => return Ok(None),
+            Ok(Some(response)) => response,
+        };
+        let headers = response.headers();
+        let has_audio_content_type = headers
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .is_some_and(|content_type| {
+                Self::AUDIO_CONTENT_TYPES
+                    .iter()
+                    .any(|audio_content_type| content_type.starts_with(audio_content_type))
+            });
+        if !(has_audio_content_type || has_audio_file_extension) {
+            return Ok(None);
+        }
+
+        let title = headers
+            .get(icy::NAME_HEADER_NAME)
+            .and_then(|name| name.to_str().ok())
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_owned)
+            .or(file_name)
+            .unwrap_or_else(|| url.to_string());
+
+        let track = Track::new(
+            title.clone(),
+            Source::HttpStream {
+                url: url.to_string(),
+                has_stream_titles: icy::metadata_interval(headers).is_some(),
+            },
+            None,
+            None,
+        );
+        Ok(Some(
+            Fetched::new(
+                title,
+                Some(url.to_string()),
+                None,
+                Box::new(FetchedSingleTrack::new(Some(track))),
+            )
+            .with_track_count(1),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_non_public_addresses() {
+        for address in [
+            "127.0.0.1",
+            "10.0.0.1",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public_address(address.parse().unwrap()), "{address}");
+        }
+    }
+
+    #[test]
+    fn accepts_public_addresses() {
+        for address in ["1.1.1.1", "2606:4700:4700::1111"] {
+            assert!(is_public_address(address.parse().unwrap()), "{address}");
+        }
+    }
+}
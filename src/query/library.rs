@@ -0,0 +1,32 @@
+use crate::library::Library;
+use crate::model::Track;
+use crate::query;
+use crate::query::{Fetched, FetchedSingleTrack};
+use serenity::async_trait;
+use std::sync::Arc;
+
+pub(crate) struct Fetcher {
+    library: Arc<Library>,
+}
+
+impl Fetcher {
+    pub(crate) fn new(library: Arc<Library>) -> Self {
+        Self { library }
+    }
+}
+
+#[async_trait]
+impl query::Fetcher for Fetcher {
+    async fn fetch<'a>(&'a self, query: &'a str) -> anyhow::Result<Option<Fetched<'a>>> {
+        Ok(self.library.search(query).map(|entry| {
+            let track = Track::from(entry);
+            Fetched::new(
+                track.title.clone(),
+                None,
+                None,
+                Box::new(FetchedSingleTrack::new(Some(track))),
+            )
+            .with_track_count(1)
+        }))
+    }
+}
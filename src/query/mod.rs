@@ -1,21 +1,29 @@
 use crate::model::Track;
 use crate::utils::AsyncIterator;
 use serenity::async_trait;
+use std::vec::IntoIter;
 
+pub(crate) mod http;
+pub(crate) mod library;
+pub(crate) mod podcast;
 pub(crate) mod spotify;
 pub(crate) mod youtube;
 
 pub(crate) struct Fetched<'a> {
     pub(crate) title: String,
-    pub(crate) url: String,
+    pub(crate) url: Option<String>,
     pub(crate) thumbnail_url: Option<String>,
     pub(crate) tracks: Box<dyn AsyncIterator<Item = anyhow::Result<Track>> + 'a + Send + Sync>,
+    /// How many tracks get enqueued unless the user asks for a different count, `None` for all.
+    pub(crate) default_track_limit: Option<usize>,
+    /// The total count of the tracks, if known before fetching them all.
+    pub(crate) track_count: Option<usize>,
 }
 
 impl<'a> Fetched<'a> {
     pub(crate) fn new(
         title: String,
-        url: String,
+        url: Option<String>,
         thumbnail_url: Option<String>,
         tracks: Box<dyn AsyncIterator<Item = anyhow::Result<Track>> + 'a + Send + Sync>,
     ) -> Self {
@@ -24,8 +32,20 @@ impl<'a> Fetched<'a> {
             url,
             thumbnail_url,
             tracks,
+            default_track_limit: None,
+            track_count: None,
         }
     }
+
+    pub(crate) fn with_default_track_limit(mut self, default_track_limit: usize) -> Self {
+        self.default_track_limit = Some(default_track_limit);
+        self
+    }
+
+    pub(crate) fn with_track_count(mut self, track_count: usize) -> Self {
+        self.track_count = Some(track_count);
+        self
+    }
 }
 
 pub(crate) struct FetchedSingleTrack {
@@ -47,6 +67,28 @@ impl AsyncIterator for FetchedSingleTrack {
     }
 }
 
+/// Tracks which are already known, not fetched lazily.
+pub(crate) struct FetchedTrackList {
+    tracks: IntoIter<Track>,
+}
+
+impl FetchedTrackList {
+    pub(crate) fn new(tracks: Vec<Track>) -> Self {
+        Self {
+            tracks: tracks.into_iter(),
+        }
+    }
+}
+
+#[async_trait]
+impl AsyncIterator for FetchedTrackList {
+    type Item = anyhow::Result<Track>;
+
+    async fn next(&mut self) -> Option<Self::Item> {
+        self.tracks.next().map(Ok)
+    }
+}
+
 #[async_trait]
 pub(crate) trait Fetcher {
     async fn fetch<'a>(&'a self, query: &'a str) -> anyhow::Result<Option<Fetched<'a>>>;
@@ -0,0 +1,262 @@
+use crate::model::{Source, Track};
+use crate::query;
+use crate::query::{Fetched, FetchedTrackList};
+use feed_rs::model::{Entry, Feed};
+use log::error;
+use reqwest::Url;
+use reqwest::header::{CONTENT_TYPE, HeaderMap};
+use serenity::async_trait;
+use std::time::{Duration, SystemTime};
+
+/// Recognizes RSS and Atom podcast feeds, the episodes are fetched from the newest.
+pub(crate) struct Fetcher {
+    http_client: reqwest::Client,
+}
+
+impl Fetcher {
+    const FEED_CONTENT_TYPES: [&'static str; 4] = [
+        "application/rss+xml",
+        "application/atom+xml",
+        "application/xml",
+        "text/xml",
+    ];
+    const ENCLOSURE_LINK_RELATION: &'static str = "enclosure";
+    /// Only the latest episode is enqueued unless a different count is requested.
+    const DEFAULT_EPISODE_COUNT: usize = 1;
+    /// Bounds the whole download of the feed, so that an unresponsive server does not hold up the
+    /// other fetchers.
+    const RESPONSE_TIMEOUT: Duration = Duration::from_secs(15);
+
+    pub(crate) fn new(http_client: reqwest::Client) -> Self {
+        Self { http_client }
+    }
+
+    fn episode(feed: &Feed, entry: &Entry) -> Option<Track> {
+        let (url, duration) = entry
+            .media
+            .iter()
+            .flat_map(|media_object| media_object.content.iter())
+            .find(|media_content| {
+                media_content
+                    .content_type
+                    .as_ref()
+                    .is_none_or(|content_type| content_type.type_().as_str() == "audio")
+            })
+            .and_then(|media_content| {
+                Some((
+                    media_content.url.as_ref()?.to_string(),
+                    media_content.duration,
+                ))
+            })
+            .or_else(|| {
+                entry
+                    .links
+                    .iter()
+                    .find(|link| link.rel.as_deref() == Some(Self::ENCLOSURE_LINK_RELATION))
+                    .map(|link| (link.href.clone(), None))
+            })?;
+
+        let mut track = Track::new(
+            entry
+                .title
+                .as_ref()
+                .map(|title| title.content.clone())
+                .unwrap_or_else(|| url.clone()),
+            Source::HttpStream {
+                url,
+                has_stream_titles: false,
+            },
+            entry
+                .media
+                .iter()
+                .flat_map(|media_object| media_object.thumbnails.iter())
+                .map(|thumbnail| thumbnail.image.uri.clone())
+                .next()
+                .or_else(|| Self::feed_image_url(feed)),
+            duration,
+        );
+        track.artist = feed.title.as_ref().map(|title| title.content.clone());
+        track.published = entry.published.or(entry.updated).map(SystemTime::from);
+        Some(track)
+    }
+
+    fn feed_image_url(feed: &Feed) -> Option<String> {
+        feed.logo
+            .as_ref()
+            .or(feed.icon.as_ref())
+            .map(|image| image.uri.clone())
+    }
+
+    /// Returns `None` if the content is not a feed with any episodes.
+    fn parse(url: &Url, content: &[u8]) -> Option<Fetched<'static>> {
+        let feed = feed_rs::parser::parse(content).ok()?;
+
+        let mut entries = feed.entries.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.published.or(entry.updated)));
+        let episodes = entries
+            .into_iter()
+            .filter_map(|entry| Self::episode(&feed, entry))
+            .collect::<Vec<_>>();
+        if episodes.is_empty() {
+            return None;
+        }
+        let episode_count = episodes.len();
+
+        Some(
+            Fetched::new(
+                feed.title
+                    .as_ref()
+                    .map(|title| title.content.clone())
+                    .unwrap_or_else(|| url.to_string()),
+                Some(
+                    feed.links
+                        .first()
+                        .map(|link| link.href.clone())
+                        .unwrap_or_else(|| url.to_string()),
+                ),
+                Self::feed_image_url(&feed),
+                Box::new(FetchedTrackList::new(episodes)),
+            )
+            .with_default_track_limit(Self::DEFAULT_EPISODE_COUNT)
+            .with_track_count(episode_count),
+        )
+    }
+}
+
+#[async_trait]
+impl query::Fetcher for Fetcher {
+    async fn fetch<'a>(&'a self, query: &'a str) -> anyhow::Result<Option<Fetched<'a>>> {
+        let url = match query::http::parse_url(query) {
+            None => return Ok(None),
+            Some(url) => url,
+        };
+
+        // A failed request only means the query is not a feed, the other fetchers may still
+        // recognize it.
+        let (url, response) = match query::http::get_public(
+            &self.http_client,
+            url,
+            HeaderMap::new(),
+            Self::RESPONSE_TIMEOUT,
+        )
+        .await
+        {
+            Err(error) => {
+                error!("{error}");
+                return Ok(None);
+            }
+            Ok(None) => return Ok(None),
+            Ok(Some(response)) => response,
+        };
+        let is_feed = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            // Without the parameters, e.g. the charset.
+            .and_then(|content_type| content_type.split(';').next())
+            .is_some_and(|content_type| {
+                Self::FEED_CONTENT_TYPES.iter().any(|feed_content_type| {
+                    content_type.trim().eq_ignore_ascii_case(feed_content_type)
+                })
+            });
+        if !is_feed {
+            return Ok(None);
+        }
+
+        let content = match response.bytes().await {
+            Err(error) => {
+                error!("{error}");
+                return Ok(None);
+            }
+            Ok(content) => content,
+        };
+
+        Ok(Self::parse(&url, &content))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::AsyncIterator;
+    use futures::executor::block_on;
+    use std::time::UNIX_EPOCH;
+
+    const FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0">
+  <channel>
+    <title>Podcast</title>
+    <link>https://example.com/podcast</link>
+    <item>
+      <title>Episode 2</title>
+      <pubDate>Thu, 01 Feb 2024 00:00:00 GMT</pubDate>
+      <enclosure url="https://example.com/2.mp3" length="1" type="audio/mpeg"/>
+    </item>
+    <item>
+      <title>Episode 3</title>
+      <pubDate>Fri, 01 Mar 2024 00:00:00 GMT</pubDate>
+      <enclosure url="https://example.com/3.mp3" length="1" type="audio/mpeg"/>
+    </item>
+    <item>
+      <title>Article</title>
+      <pubDate>Sat, 01 Jun 2024 00:00:00 GMT</pubDate>
+    </item>
+    <item>
+      <title>Episode 1</title>
+      <pubDate>Mon, 01 Jan 2024 00:00:00 GMT</pubDate>
+      <enclosure url="https://example.com/1.mp3" length="1" type="audio/mpeg"/>
+    </item>
+  </channel>
+</rss>"#;
+
+    fn parse_feed() -> Fetched<'static> {
+        let url = Url::parse("https://example.com/feed.xml").unwrap();
+        Fetcher::parse(&url, FEED.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn parse_orders_episodes_from_newest() {
+        let mut fetched = parse_feed();
+
+        let mut titles = Vec::new();
+        while let Some(track) = block_on(fetched.tracks.next()) {
+            titles.push(track.unwrap().title);
+        }
+
+        assert_eq!(titles, ["Episode 3", "Episode 2", "Episode 1"]);
+        assert_eq!(fetched.track_count, Some(3));
+    }
+
+    #[test]
+    fn parse_limits_to_latest_episode_by_default() {
+        let fetched = parse_feed();
+
+        assert_eq!(fetched.default_track_limit, Some(1));
+        assert_eq!(fetched.title, "Podcast");
+        assert_eq!(fetched.url.as_deref(), Some("https://example.com/podcast"));
+    }
+
+    #[test]
+    fn parse_reads_enclosure_and_publish_date() {
+        let mut fetched = parse_feed();
+
+        let track = block_on(fetched.tracks.next()).unwrap().unwrap();
+
+        assert!(matches!(
+            track.source,
+            Source::HttpStream { url, .. } if url == "https://example.com/3.mp3"
+        ));
+        assert_eq!(
+            track.published,
+            Some(UNIX_EPOCH + Duration::from_secs(1_709_251_200))
+        );
+        assert_eq!(track.artist.as_deref(), Some("Podcast"));
+    }
+
+    #[test]
+    fn parse_rejects_non_feed() {
+        let url = Url::parse("https://example.com/page.html").unwrap();
+
+        assert!(Fetcher::parse(&url, b"<html><body></body></html>").is_none());
+    }
+}
@@ -0,0 +1,194 @@
+use crate::model::{Source, Track};
+use amplify_derive::Display;
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+use thiserror::Error;
+use tokio::fs;
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+use unwrap_or_log::LogError;
+
+const DIRECTORY_PATH: &str = "cache";
+const FILE_NAME: &str = "spotify.json";
+/// Written first and then renamed over the cache file, so that an interrupted write does not
+/// corrupt it.
+const TEMPORARY_FILE_NAME: &str = "spotify.json.tmp";
+/// Changes are written in batches, so that resolving a long playlist does not rewrite the whole
+/// file for every single track.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+/// Older matches are searched for again, since the videos may have been taken down meanwhile.
+const ENTRY_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+/// Beyond this count, the oldest matches are evicted.
+const MAX_ENTRY_COUNT: usize = 10_000;
+
+#[derive(Error, Display, Debug)]
+#[display(Debug)]
+#[allow(dead_code)]
+pub(crate) enum Error {
+    Io(std::io::Error),
+    Serialization(serde_json::Error),
+}
+
+impl From<std::io::Error> for Error {
+    fn from(io_error: std::io::Error) -> Self {
+        Self::Io(io_error)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(serde_json_error: serde_json::Error) -> Self {
+        Self::Serialization(serde_json_error)
+    }
+}
+
+/// The YouTube video found for a Spotify track.
+#[derive(Serialize, Deserialize, Clone)]
+struct Entry {
+    url: String,
+    title: String,
+    thumbnail_url: Option<String>,
+    /// Kept, so that the limit of the track duration applies to the cached tracks as well.
+    #[serde(default)]
+    duration: Option<Duration>,
+    cached_at: SystemTime,
+}
+
+impl Entry {
+    fn is_expired(&self) -> bool {
+        self.cached_at
+            .elapsed()
+            .is_ok_and(|elapsed| elapsed > ENTRY_TTL)
+    }
+}
+
+/// Persists the YouTube videos matched to the Spotify tracks on disk, keyed by the Spotify track
+/// ID, so that the same tracks are not searched for again.
+pub(crate) struct Cache {
+    entries: Mutex<HashMap<String, Entry>>,
+    has_unsaved_changes: AtomicBool,
+}
+
+impl Cache {
+    /// A cache file which cannot be read is logged and the cache starts empty, since the matches
+    /// can always be searched for again. The changes are written until the cache is dropped.
+    pub(crate) async fn new() -> Result<Arc<Self>, Error> {
+        fs::create_dir_all(DIRECTORY_PATH).await?;
+
+        let mut entries = match Self::load().await {
+            Err(error) => {
+                error!("{error}");
+                HashMap::new()
+            }
+            Ok(entries) => entries,
+        };
+        entries.retain(|_, entry| !entry.is_expired());
+
+        let new = Arc::new(Self {
+            entries: Mutex::new(entries),
+            has_unsaved_changes: AtomicBool::new(false),
+        });
+
+        {
+            let cache = Arc::downgrade(&new);
+            tokio::spawn(async move {
+                loop {
+                    sleep(FLUSH_INTERVAL).await;
+                    let cache = match cache.upgrade() {
+                        None => return,
+                        Some(cache) => cache,
+                    };
+                    _ = cache.flush().await.log_error();
+                }
+            });
+        }
+
+        Ok(new)
+    }
+
+    async fn load() -> Result<HashMap<String, Entry>, Error> {
+        match fs::read(Self::file_path()).await {
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(error) => Err(error)?,
+            Ok(content) => Ok(serde_json::from_slice(&content)?),
+        }
+    }
+
+    fn file_path() -> PathBuf {
+        Path::new(DIRECTORY_PATH).join(FILE_NAME)
+    }
+
+    /// The track carries only the details of the video, not the metadata from Spotify.
+    pub(crate) async fn get(&self, spotify_track_id: &str) -> Option<Track> {
+        let mut entries = self.entries.lock().await;
+        let entry = entries.get(spotify_track_id)?;
+        if entry.is_expired() {
+            entries.remove(spotify_track_id);
+            self.has_unsaved_changes.store(true, Ordering::Relaxed);
+            return None;
+        }
+
+        Some(Track::new(
+            entry.title.clone(),
+            Source::YouTube {
+                url: entry.url.clone(),
+            },
+            entry.thumbnail_url.clone(),
+            entry.duration,
+        ))
+    }
+
+    /// Only tracks found on YouTube are cached.
+    pub(crate) async fn insert(&self, spotify_track_id: String, track: &Track) {
+        let url = match &track.source {
+            Source::YouTube { url } => url.clone(),
+            _ => return,
+        };
+
+        let mut entries = self.entries.lock().await;
+        entries.insert(
+            spotify_track_id,
+            Entry {
+                url,
+                title: track.title.clone(),
+                thumbnail_url: track.thumbnail_url.clone(),
+                duration: track.duration,
+                cached_at: SystemTime::now(),
+            },
+        );
+        if entries.len() > MAX_ENTRY_COUNT
+            && let Some(oldest_spotify_track_id) = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.cached_at)
+                .map(|(spotify_track_id, _)| spotify_track_id.clone())
+        {
+            entries.remove(&oldest_spotify_track_id);
+        }
+        self.has_unsaved_changes.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns whether any match has been cached for the track.
+    pub(crate) async fn remove(&self, spotify_track_id: &str) -> bool {
+        let is_removed = self.entries.lock().await.remove(spotify_track_id).is_some();
+        if is_removed {
+            self.has_unsaved_changes.store(true, Ordering::Relaxed);
+        }
+        is_removed
+    }
+
+    async fn flush(&self) -> Result<(), Error> {
+        if !self.has_unsaved_changes.swap(false, Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        let content = serde_json::to_vec(&*self.entries.lock().await)?;
+        let temporary_file_path = Path::new(DIRECTORY_PATH).join(TEMPORARY_FILE_NAME);
+        fs::write(&temporary_file_path, content).await?;
+        fs::rename(temporary_file_path, Self::file_path()).await?;
+        Ok(())
+    }
+}
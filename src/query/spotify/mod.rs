@@ -1,5 +1,8 @@
-use rspotify::model::IdError;
+use crate::model::Track;
+use crate::youtube;
+use rspotify::model::{FullTrack, Id, IdError, TrackId};
 
+pub(crate) mod cache;
 pub(crate) mod playlist;
 pub(crate) mod track;
 
@@ -31,7 +34,49 @@ trait ToSearchQuery {
     }
 }
 
-impl ToSearchQuery for rspotify::model::FullTrack {
+/// Accepts a link to a Spotify track or its bare ID.
+pub(crate) fn parse_track_id(text: &str) -> Option<String> {
+    <track::Fetcher as Fetcher>::parse_id(text)
+        .or_else(|_| TrackId::from_id(text.trim()).map_err(|_| ()))
+        .ok()
+        .map(|id| id.id().to_owned())
+}
+
+/// Searches for the Spotify track on YouTube, unless the match is already cached.
+async fn find_on_youtube(
+    youtube_searcher: &youtube::Searcher,
+    cache: &cache::Cache,
+    spotify_track: &FullTrack,
+) -> anyhow::Result<Option<Track>> {
+    let spotify_track_id = spotify_track.id.as_ref().map(|id| id.id().to_owned());
+    if let Some(spotify_track_id) = &spotify_track_id
+        && let Some(track) = cache.get(spotify_track_id).await
+    {
+        return Ok(Some(with_spotify_metadata(track, spotify_track)));
+    }
+
+    let found_track = youtube_searcher
+        .search(spotify_track.to_search_query())
+        .await?;
+    if let (Some(spotify_track_id), Some(found_track)) = (spotify_track_id, &found_track) {
+        cache.insert(spotify_track_id, found_track).await;
+    }
+
+    Ok(found_track.map(|found_track| with_spotify_metadata(found_track, spotify_track)))
+}
+
+/// Completes the track found on YouTube with the metadata from Spotify.
+fn with_spotify_metadata(mut track: Track, spotify_track: &FullTrack) -> Track {
+    track.artist = spotify_track
+        .artists
+        .first()
+        .map(|artist| artist.name.clone());
+    track.album = Some(spotify_track.album.name.clone());
+    track.original_url = spotify_track.id.as_ref().map(|id| id.url());
+    track
+}
+
+impl ToSearchQuery for FullTrack {
     fn title(&self) -> impl AsRef<str> {
         self.name.as_str()
     }
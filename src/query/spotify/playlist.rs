@@ -1,55 +1,140 @@
+use crate::env::spotify::{PLAYLIST_SEARCH_CONCURRENCY, PLAYLIST_SEARCH_TIMEOUT};
 use crate::model::Track;
 use crate::query::Fetched;
-use crate::query::spotify::ToSearchQuery;
+use crate::query::spotify::cache::Cache;
+use crate::query::spotify::find_on_youtube;
 use crate::utils::AsyncIterator;
 use crate::{query, youtube};
 use rspotify::clients::BaseClient;
 use rspotify::clients::pagination::Paginator;
 use rspotify::http::HttpError;
-use rspotify::model::{Id, IdError, PlayableItem, PlaylistId, PlaylistItem};
+use rspotify::model::{FullTrack, Id, IdError, PlayableItem, PlaylistId, PlaylistItem};
 use rspotify::{ClientCredsSpotify, ClientError, ClientResult};
 use serenity::async_trait;
 use serenity::futures::StreamExt;
+use std::collections::VecDeque;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
 
+/// A track of the playlist, searched for on YouTube in the background.
+enum PendingTrack {
+    Searching(JoinHandle<anyhow::Result<Option<Track>>>),
+    Failed(anyhow::Error),
+}
+
+/// Searches for several tracks at once, yet yields them in the order of the playlist.
 pub(crate) struct FetchedTracks<'a> {
-    youtube_searcher: &'a youtube::Searcher,
+    youtube_searcher: Arc<youtube::Searcher>,
+    cache: Arc<Cache>,
     items: Arc<Mutex<Paginator<'a, ClientResult<PlaylistItem>>>>,
+    pending_tracks: VecDeque<PendingTrack>,
+    items_exhausted: bool,
 }
 
 impl<'a> FetchedTracks<'a> {
     pub(crate) fn new(
-        youtube_searcher: &'a youtube::Searcher,
+        youtube_searcher: Arc<youtube::Searcher>,
+        cache: Arc<Cache>,
         items: Arc<Mutex<Paginator<'a, ClientResult<PlaylistItem>>>>,
     ) -> Self {
         Self {
             youtube_searcher,
+            cache,
             items,
+            pending_tracks: VecDeque::new(),
+            items_exhausted: false,
+        }
+    }
+
+    /// Starts searching for the following items, up to the limit of the searches at once.
+    async fn start_searches(&mut self) {
+        let search_concurrency: usize = PLAYLIST_SEARCH_CONCURRENCY
+            .parse()
+            .ok()
+            .filter(|search_concurrency| *search_concurrency > 0)
+            .unwrap_or(1);
+        let mut items = self.items.lock().await;
+
+        while !self.items_exhausted && self.pending_tracks.len() < search_concurrency {
+            match items.next().await {
+                None => self.items_exhausted = true,
+                Some(Err(error)) => self
+                    .pending_tracks
+                    .push_back(PendingTrack::Failed(error.into())),
+                Some(Ok(PlaylistItem {
+                    track: Some(PlayableItem::Track(track)),
+                    ..
+                })) => {
+                    let youtube_searcher = self.youtube_searcher.clone();
+                    let cache = self.cache.clone();
+                    self.pending_tracks
+                        .push_back(PendingTrack::Searching(tokio::spawn(async move {
+                            search(&youtube_searcher, &cache, &track).await
+                        })));
+                }
+                // Yielded as failed, so that they count among the fetched items of the playlist,
+                // whose total count includes them.
+                Some(Ok(_)) => {
+                    self.pending_tracks
+                        .push_back(PendingTrack::Failed(anyhow::anyhow!(
+                            "a playlist item is not a track, e.g. an episode or a local file"
+                        )))
+                }
+            }
         }
     }
 }
 
+/// Gives up once the timeout elapses, so that a single stuck search does not hold up the others.
+async fn search(
+    youtube_searcher: &youtube::Searcher,
+    cache: &Cache,
+    spotify_track: &FullTrack,
+) -> anyhow::Result<Option<Track>> {
+    let search = find_on_youtube(youtube_searcher, cache, spotify_track);
+    let timeout = PLAYLIST_SEARCH_TIMEOUT
+        .parse()
+        .ok()
+        .filter(|seconds| *seconds > 0)
+        .map(Duration::from_secs);
+    match timeout {
+        None => search.await,
+        Some(timeout) => tokio::time::timeout(timeout, search).await?,
+    }
+}
+
 #[async_trait]
 impl AsyncIterator for FetchedTracks<'_> {
     type Item = anyhow::Result<Track>;
 
     async fn next(&mut self) -> Option<Self::Item> {
-        let mut items = self.items.lock().await;
+        self.start_searches().await;
 
-        loop {
-            match items.next().await? {
-                Err(error) => return Some(Err(error.into())),
-                Ok(PlaylistItem {
-                    track: Some(PlayableItem::Track(track)),
-                    ..
-                }) => match self.youtube_searcher.search(track.to_search_query()).await {
-                    Err(error) => return Some(Err(error)),
-                    Ok(None) => continue,
-                    Ok(Some(track)) => return Some(Ok(track)),
-                },
-                _ => continue,
-            };
+        let found_track = match self.pending_tracks.pop_front()? {
+            PendingTrack::Failed(error) => return Some(Err(error)),
+            PendingTrack::Searching(search) => search.await,
+        };
+        Some(match found_track {
+            Err(error) => Err(error.into()),
+            Ok(Err(error)) => Err(error),
+            // Yielded as failed, so that it counts among the fetched items as well.
+            Ok(Ok(None)) => Err(anyhow::anyhow!(
+                "no video has been found on YouTube for a playlist track"
+            )),
+            Ok(Ok(Some(found_track))) => Ok(found_track),
+        })
+    }
+}
+
+impl Drop for FetchedTracks<'_> {
+    fn drop(&mut self) {
+        // The searches are not needed anymore once the import ends, e.g. when cancelled.
+        for pending_track in &self.pending_tracks {
+            if let PendingTrack::Searching(search) = pending_track {
+                search.abort();
+            }
         }
     }
 }
@@ -57,6 +142,7 @@ impl AsyncIterator for FetchedTracks<'_> {
 pub(crate) struct Fetcher {
     spotify_client: Arc<ClientCredsSpotify>,
     youtube_searcher: Arc<youtube::Searcher>,
+    cache: Arc<Cache>,
 }
 
 impl Fetcher {
@@ -66,10 +152,12 @@ impl Fetcher {
     pub(crate) fn new(
         spotify_client: Arc<ClientCredsSpotify>,
         youtube_searcher: Arc<youtube::Searcher>,
+        cache: Arc<Cache>,
     ) -> Self {
         Self {
             spotify_client,
             youtube_searcher,
+            cache,
         }
     }
 }
@@ -114,18 +202,22 @@ impl query::Fetcher for Fetcher {
 
         let playlist_items = self.spotify_client.playlist_items(id, None, None);
 
-        Ok(Some(Fetched::new(
-            playlist.name,
-            playlist.id.url(),
-            playlist
-                .images
-                .into_iter()
-                .max_by_key(|image| image.width)
-                .map(|image| image.url),
-            Box::new(FetchedTracks::new(
-                self.youtube_searcher.as_ref(),
-                Arc::new(Mutex::new(playlist_items)),
-            )),
-        )))
+        Ok(Some(
+            Fetched::new(
+                playlist.name,
+                Some(playlist.id.url()),
+                playlist
+                    .images
+                    .into_iter()
+                    .max_by_key(|image| image.width)
+                    .map(|image| image.url),
+                Box::new(FetchedTracks::new(
+                    self.youtube_searcher.clone(),
+                    self.cache.clone(),
+                    Arc::new(Mutex::new(playlist_items)),
+                )),
+            )
+            .with_track_count(playlist.tracks.total as usize),
+        ))
     }
 }
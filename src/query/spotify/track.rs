@@ -1,4 +1,5 @@
-use crate::query::spotify::ToSearchQuery;
+use crate::query::spotify::cache::Cache;
+use crate::query::spotify::find_on_youtube;
 use crate::query::{Fetched, FetchedSingleTrack};
 use crate::{query, youtube};
 use rspotify::clients::BaseClient;
@@ -11,6 +12,7 @@ use std::sync::Arc;
 pub(crate) struct Fetcher {
     spotify_client: Arc<ClientCredsSpotify>,
     youtube_searcher: Arc<youtube::Searcher>,
+    cache: Arc<Cache>,
 }
 
 impl Fetcher {
@@ -20,10 +22,12 @@ impl Fetcher {
     pub(crate) fn new(
         spotify_client: Arc<ClientCredsSpotify>,
         youtube_searcher: Arc<youtube::Searcher>,
+        cache: Arc<Cache>,
     ) -> Self {
         Self {
             spotify_client,
             youtube_searcher,
+            cache,
         }
     }
 }
@@ -66,24 +70,24 @@ impl query::Fetcher for Fetcher {
             }
         };
 
-        let track = self
-            .youtube_searcher
-            .search(spotify_track.to_search_query())
-            .await?;
+        let track = find_on_youtube(&self.youtube_searcher, &self.cache, &spotify_track).await?;
 
-        Ok(Some(Fetched::new(
-            spotify_track.name,
-            match spotify_track.id {
-                None => return Ok(None),
-                Some(id) => id.url(),
-            },
-            spotify_track
-                .album
-                .images
-                .into_iter()
-                .max_by_key(|image| image.width)
-                .map(|image| image.url),
-            Box::new(FetchedSingleTrack::new(track)),
-        )))
+        Ok(Some(
+            Fetched::new(
+                spotify_track.name,
+                match spotify_track.id {
+                    None => return Ok(None),
+                    Some(id) => Some(id.url()),
+                },
+                spotify_track
+                    .album
+                    .images
+                    .into_iter()
+                    .max_by_key(|image| image.width)
+                    .map(|image| image.url),
+                Box::new(FetchedSingleTrack::new(track)),
+            )
+            .with_track_count(1),
+        ))
     }
 }
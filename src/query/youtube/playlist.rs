@@ -1,11 +1,12 @@
-use crate::model::Track;
+use crate::model::{Source, Track};
 use crate::query;
 use crate::query::Fetched;
 use crate::utils::AsyncIterator;
+use crate::youtube;
 use rustypipe::client::RustyPipe;
 use rustypipe::model::{UrlTarget, VideoItem};
-use rustypipe::param::{Country, Language};
 use serenity::async_trait;
+use std::time::Duration;
 use std::vec::IntoIter;
 
 pub(crate) struct FetchedTracks {
@@ -35,17 +36,9 @@ pub(crate) struct Fetcher {
 
 impl Fetcher {
     const PLAYLIST_ITEMS_FETCH_COUNT_LIMIT: usize = 1_000;
-    const RESOLVE_YOUTUBE_MUSIC_ALBUM_IDS: bool = false;
-    const RUSTY_PIPE_STORAGE_DIRECTORY_PATH: &'static str = "rusty_pipe_storage";
 
-    pub(crate) fn new() -> Result<Self, rustypipe::error::Error> {
-        Ok(Self {
-            rusty_pipe_client: RustyPipe::builder()
-                .country(Country::Cz)
-                .lang(Language::Cs)
-                .storage_dir(Self::RUSTY_PIPE_STORAGE_DIRECTORY_PATH)
-                .build()?,
-        })
+    pub(crate) fn new(rusty_pipe_client: RustyPipe) -> Self {
+        Self { rusty_pipe_client }
     }
 }
 
@@ -55,7 +48,7 @@ impl query::Fetcher for Fetcher {
         let (id, url) = match self
             .rusty_pipe_client
             .query()
-            .resolve_url(query, Self::RESOLVE_YOUTUBE_MUSIC_ALBUM_IDS)
+            .resolve_url(query, youtube::RESOLVE_YOUTUBE_MUSIC_ALBUM_IDS)
             .await
         {
             Ok(url_target) => {
@@ -86,33 +79,44 @@ impl query::Fetcher for Fetcher {
             .extend_limit(query, Self::PLAYLIST_ITEMS_FETCH_COUNT_LIMIT)
             .await?;
 
-        Ok(Some(Fetched::new(
-            playlist.name,
-            url,
-            playlist
-                .thumbnail
-                .into_iter()
-                .max_by_key(|thumbnail| thumbnail.width)
-                .map(|thumbnail| thumbnail.url),
-            Box::new(FetchedTracks::new(playlist.videos.items)),
-        )))
+        let track_count = playlist.videos.items.len();
+        Ok(Some(
+            Fetched::new(
+                playlist.name,
+                Some(url),
+                playlist
+                    .thumbnail
+                    .into_iter()
+                    .max_by_key(|thumbnail| thumbnail.width)
+                    .map(|thumbnail| thumbnail.url),
+                Box::new(FetchedTracks::new(playlist.videos.items)),
+            )
+            .with_track_count(track_count),
+        ))
     }
 }
 
 impl From<VideoItem> for Track {
     fn from(video_item: VideoItem) -> Self {
-        Self::new(
+        let mut track = Self::new(
             video_item.name,
-            UrlTarget::Video {
-                id: video_item.id,
-                start_time: 0,
-            }
-            .to_url(),
+            Source::YouTube {
+                url: UrlTarget::Video {
+                    id: video_item.id,
+                    start_time: 0,
+                }
+                .to_url(),
+            },
             video_item
                 .thumbnail
                 .into_iter()
                 .max_by_key(|thumbnail| thumbnail.width)
                 .map(|thumbnail| thumbnail.url),
-        )
+            video_item
+                .duration
+                .map(|seconds| Duration::from_secs(seconds.into())),
+        );
+        track.artist = video_item.channel.map(|channel| channel.name);
+        track
     }
 }
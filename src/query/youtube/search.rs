@@ -20,10 +20,11 @@ impl query::Fetcher for Fetcher {
             track.map(|track| {
                 Fetched::new(
                     track.title.clone(),
-                    track.youtube_url.clone(),
+                    track.url().map(str::to_owned),
                     track.thumbnail_url.clone(),
                     Box::new(FetchedSingleTrack::new(Some(track))),
                 )
+                .with_track_count(1)
             })
         })
     }
@@ -0,0 +1,172 @@
+use crate::player::Queue;
+use amplify_derive::Display;
+use log::error;
+use serde::{Deserialize, Serialize};
+use serenity::all::{ChannelId, GuildId};
+use std::collections::HashMap;
+use std::num::NonZeroU64;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::fs;
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+use unwrap_or_log::LogError;
+
+const DIRECTORY_PATH: &str = "sessions";
+const FILE_EXTENSION: &str = "json";
+/// Appended to the file of a session while it is being written, so that an interrupted write does
+/// not corrupt the session.
+const TEMPORARY_FILE_EXTENSION: &str = "json.tmp";
+/// Changes are written in batches, so that e.g. enqueueing a long playlist track by track does not
+/// rewrite the whole session file for every single track.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Error, Display, Debug)]
+#[display(Debug)]
+#[allow(dead_code)]
+pub(crate) enum Error {
+    Io(std::io::Error),
+    Serialization(serde_json::Error),
+}
+
+impl From<std::io::Error> for Error {
+    fn from(io_error: std::io::Error) -> Self {
+        Self::Io(io_error)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(serde_json_error: serde_json::Error) -> Self {
+        Self::Serialization(serde_json_error)
+    }
+}
+
+/// The state of a player needed to resume the playback after the bot restarts.
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct Session {
+    pub(crate) queue: Queue,
+    pub(crate) repeating: bool,
+    pub(crate) repeating_queue: bool,
+    #[serde(default)]
+    pub(crate) autoplay: bool,
+    pub(crate) voice_channel_id: ChannelId,
+    pub(crate) text_channel_id: ChannelId,
+}
+
+/// Persists the sessions on disk, one file per guild.
+pub(crate) struct Manager {
+    /// `None` marks a session to be removed.
+    pending_changes: Mutex<HashMap<GuildId, Option<Session>>>,
+}
+
+impl Manager {
+    pub(crate) async fn new() -> Result<Arc<Self>, Error> {
+        fs::create_dir_all(DIRECTORY_PATH).await?;
+
+        let new = Arc::new(Self {
+            pending_changes: Mutex::new(HashMap::new()),
+        });
+
+        {
+            let new = new.clone();
+            tokio::spawn(async move {
+                loop {
+                    sleep(FLUSH_INTERVAL).await;
+                    new.flush().await;
+                }
+            });
+        }
+
+        Ok(new)
+    }
+
+    fn file_path(guild_id: GuildId) -> PathBuf {
+        Path::new(DIRECTORY_PATH).join(format!("{guild_id}.{FILE_EXTENSION}"))
+    }
+
+    pub(crate) async fn save(&self, guild_id: GuildId, session: Session) {
+        self.pending_changes
+            .lock()
+            .await
+            .insert(guild_id, Some(session));
+    }
+
+    pub(crate) async fn remove(&self, guild_id: GuildId) {
+        self.pending_changes.lock().await.insert(guild_id, None);
+    }
+
+    async fn flush(&self) {
+        let pending_changes = std::mem::take(&mut *self.pending_changes.lock().await);
+
+        for (guild_id, session) in pending_changes {
+            _ = match session {
+                None => Self::remove_file(guild_id).await,
+                Some(session) => Self::write_file(guild_id, &session).await,
+            }
+            .log_error();
+        }
+    }
+
+    async fn write_file(guild_id: GuildId, session: &Session) -> Result<(), Error> {
+        let temporary_file_path =
+            Path::new(DIRECTORY_PATH).join(format!("{guild_id}.{TEMPORARY_FILE_EXTENSION}"));
+        fs::write(&temporary_file_path, serde_json::to_vec(session)?).await?;
+        fs::rename(temporary_file_path, Self::file_path(guild_id)).await?;
+        Ok(())
+    }
+
+    async fn remove_file(guild_id: GuildId) -> Result<(), Error> {
+        match fs::remove_file(Self::file_path(guild_id)).await {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error)?,
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) async fn load(&self, guild_id: GuildId) -> Result<Option<Session>, Error> {
+        if let Some(session) = self.pending_changes.lock().await.get(&guild_id) {
+            return Ok(session.clone());
+        }
+
+        match fs::read(Self::file_path(guild_id)).await {
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error)?,
+            Ok(content) => Ok(Some(serde_json::from_slice(&content)?)),
+        }
+    }
+
+    /// Sessions that fail to load are logged and skipped.
+    pub(crate) async fn load_all(&self) -> Result<HashMap<GuildId, Session>, Error> {
+        let mut sessions = HashMap::new();
+
+        let mut entries = fs::read_dir(DIRECTORY_PATH).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path
+                .extension()
+                .is_none_or(|extension| extension != FILE_EXTENSION)
+            {
+                continue;
+            }
+            let guild_id = match path
+                .file_stem()
+                .and_then(|file_stem| file_stem.to_str())
+                .and_then(|file_stem| file_stem.parse::<NonZeroU64>().ok())
+            {
+                None => continue,
+                Some(guild_id) => GuildId::from(guild_id),
+            };
+
+            match self.load(guild_id).await {
+                Err(error) => error!("{error}"),
+                Ok(None) => (),
+                Ok(Some(session)) => {
+                    sessions.insert(guild_id, session);
+                }
+            }
+        }
+
+        Ok(sessions)
+    }
+}
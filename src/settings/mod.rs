@@ -0,0 +1,189 @@
+use crate::command::ActionKind;
+use crate::player;
+use amplify_derive::Display;
+use log::error;
+use serde::{Deserialize, Serialize};
+use serenity::all::{GuildId, Permissions, RoleId};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::fs;
+use tokio::sync::RwLock;
+
+const DIRECTORY_PATH: &str = "settings";
+const GUILDS_FILE_NAME: &str = "guilds.json";
+/// Written first and then renamed over the settings file, so that an interrupted write does not
+/// corrupt it.
+const GUILDS_TEMPORARY_FILE_NAME: &str = "guilds.json.tmp";
+const DEFAULT_VOTE_SKIP_PERCENTAGE: u8 = 50;
+
+#[derive(Error, Display, Debug)]
+#[display(Debug)]
+#[allow(dead_code)]
+pub(crate) enum Error {
+    Io(std::io::Error),
+    Serialization(serde_json::Error),
+}
+
+impl From<std::io::Error> for Error {
+    fn from(io_error: std::io::Error) -> Self {
+        Self::Io(io_error)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(serde_json_error: serde_json::Error) -> Self {
+        Self::Serialization(serde_json_error)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub(crate) struct GuildSettings {
+    /// In percent.
+    pub(crate) volume: u16,
+    /// Whether the queue interleaves the tracks of different requesters.
+    pub(crate) fair_queue: bool,
+    /// Whether a single message announcing the current track is kept up to date.
+    pub(crate) live_now_playing: bool,
+    /// Whether skipping a track requires a vote of the listeners.
+    pub(crate) vote_skip: bool,
+    /// The percentage of the listeners needed to skip a track.
+    pub(crate) vote_skip_percentage: u8,
+    /// The members of this role control the playback without restrictions.
+    pub(crate) dj_role_id: Option<RoleId>,
+    /// Overrides the default requirements of the actions.
+    pub(crate) requirements: HashMap<ActionKind, Requirement>,
+    pub(crate) limits: Limits,
+}
+
+/// `None` means no limit.
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(default)]
+pub(crate) struct Limits {
+    /// Counts the tracks which have not been played yet.
+    pub(crate) max_queue_length: Option<usize>,
+    /// Counts the tracks of a user which have not been played yet.
+    pub(crate) max_user_track_count: Option<usize>,
+    /// Tracks of an unknown duration, e.g. live streams, are not limited.
+    pub(crate) max_track_duration: Option<Duration>,
+    /// How many tracks of a playlist can be enqueued at once.
+    pub(crate) max_playlist_import_size: Option<usize>,
+}
+
+impl GuildSettings {
+    pub(crate) fn requirement(&self, action_kind: ActionKind) -> Requirement {
+        match self.requirements.get(&action_kind) {
+            Some(requirement) => *requirement,
+            None => match action_kind {
+                ActionKind::Stop
+                | ActionKind::QueueShuffle
+                | ActionKind::QueueMove
+                | ActionKind::QueueRepeat
+                | ActionKind::Repeat => Requirement::Dj,
+                _ => Requirement::Everyone,
+            },
+        }
+    }
+}
+
+/// What a member needs to perform an action. The server managers are never restricted.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Requirement {
+    Everyone,
+    /// Met by everyone while no DJ role is set.
+    Dj,
+    Role(RoleId),
+    Permissions(Permissions),
+}
+
+impl Requirement {
+    /// Covers the requirements not needing any further parameters.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "vsichni" => Some(Self::Everyone),
+            "dj" => Some(Self::Dj),
+            "moderatori" => Some(Self::Permissions(Permissions::MOVE_MEMBERS)),
+            "spravci" => Some(Self::Permissions(Permissions::MANAGE_GUILD)),
+            _ => None,
+        }
+    }
+}
+
+impl Default for GuildSettings {
+    fn default() -> Self {
+        Self {
+            volume: player::DEFAULT_VOLUME,
+            fair_queue: false,
+            live_now_playing: false,
+            vote_skip: false,
+            vote_skip_percentage: DEFAULT_VOTE_SKIP_PERCENTAGE,
+            dj_role_id: None,
+            requirements: HashMap::new(),
+            limits: Limits::default(),
+        }
+    }
+}
+
+/// Keeps the per-guild settings and persists them on disk, so that they survive bot restarts.
+pub(crate) struct Manager {
+    guilds: RwLock<HashMap<GuildId, GuildSettings>>,
+}
+
+impl Manager {
+    /// A settings file which cannot be read is logged and all the guilds start with the defaults,
+    /// so that the bot still comes up.
+    pub(crate) async fn new() -> Result<Self, Error> {
+        fs::create_dir_all(DIRECTORY_PATH).await?;
+
+        let guilds = match Self::load_guilds().await {
+            Err(error) => {
+                error!("{error}");
+                HashMap::new()
+            }
+            Ok(guilds) => guilds,
+        };
+
+        Ok(Self {
+            guilds: RwLock::new(guilds),
+        })
+    }
+
+    async fn load_guilds() -> Result<HashMap<GuildId, GuildSettings>, Error> {
+        match fs::read(Self::guilds_file_path()).await {
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(error) => Err(error)?,
+            Ok(content) => Ok(serde_json::from_slice(&content)?),
+        }
+    }
+
+    fn guilds_file_path() -> PathBuf {
+        Path::new(DIRECTORY_PATH).join(GUILDS_FILE_NAME)
+    }
+
+    pub(crate) async fn guild(&self, guild_id: GuildId) -> GuildSettings {
+        self.guilds
+            .read()
+            .await
+            .get(&guild_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub(crate) async fn update_guild(
+        &self,
+        guild_id: GuildId,
+        update: impl FnOnce(&mut GuildSettings),
+    ) -> Result<(), Error> {
+        let mut guilds = self.guilds.write().await;
+        update(guilds.entry(guild_id).or_default());
+
+        let temporary_file_path = Path::new(DIRECTORY_PATH).join(GUILDS_TEMPORARY_FILE_NAME);
+        fs::write(&temporary_file_path, serde_json::to_vec_pretty(&*guilds)?).await?;
+        fs::rename(temporary_file_path, Self::guilds_file_path()).await?;
+
+        Ok(())
+    }
+}
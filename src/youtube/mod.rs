@@ -1,49 +1,135 @@
-use crate::model::Track;
-use songbird::input::{AudioStreamError, AuxMetadata, YoutubeDl};
+use crate::model::{Source, Track};
+use rustypipe::client::RustyPipe;
+use rustypipe::model::UrlTarget;
+use rustypipe::param::{Country, Language};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::process::Command;
 
+const YT_DLP_PROGRAM: &str = "yt-dlp";
 const MAX_RESULTS: usize = 5;
+const RUSTY_PIPE_STORAGE_DIRECTORY_PATH: &str = "rusty_pipe_storage";
+pub(crate) const RESOLVE_YOUTUBE_MUSIC_ALBUM_IDS: bool = false;
 
-pub(crate) struct Searcher {
-    http_client: reqwest::Client,
+/// The client is meant to be shared, so that all its users use the same storage.
+pub(crate) fn create_rusty_pipe_client() -> Result<RustyPipe, rustypipe::error::Error> {
+    RustyPipe::builder()
+        .country(Country::Cz)
+        .lang(Language::Cs)
+        .storage_dir(RUSTY_PIPE_STORAGE_DIRECTORY_PATH)
+        .build()
 }
 
+/// A video found by yt-dlp, printed as a single line of JSON.
+#[derive(Deserialize)]
+struct SearchResult {
+    title: Option<String>,
+    webpage_url: Option<String>,
+    thumbnail: Option<String>,
+    /// In seconds.
+    duration: Option<f64>,
+    artist: Option<String>,
+    album: Option<String>,
+    channel: Option<String>,
+}
+
+pub(crate) struct Searcher;
+
 impl Searcher {
-    pub(crate) fn new(http_client: reqwest::Client) -> Self {
-        Self { http_client }
+    pub(crate) fn new() -> Self {
+        Self
     }
 
+    /// The yt-dlp process is killed once the returned future is dropped, e.g. when the search
+    /// times out or the import is cancelled.
     pub(crate) async fn search(
         &self,
         query: impl AsRef<str>,
     ) -> Result<Option<Track>, anyhow::Error> {
-        YoutubeDl::new_search(self.http_client.clone(), query.as_ref())
-            .search(Some(MAX_RESULTS))
-            .await
-            .map(Some)
-            .or_else(|error| match error {
-                AudioStreamError::Fail(error) => {
-                    if error.to_string().contains("no results found") {
-                        Ok(None)
-                    } else {
-                        Err(anyhow::Error::from_boxed(error))
-                    }
-                }
-                error => Err(error.into()),
-            })
-            .map(|results| {
-                results.and_then(|results| results.map(Track::try_from).find_map(Result::ok))
-            })
+        let search_query = format!("ytsearch{MAX_RESULTS}:{}", query.as_ref());
+        let output = Command::new(YT_DLP_PROGRAM)
+            .args([
+                "-j",
+                search_query.as_str(),
+                "-f",
+                "ba[abr>0][vcodec=none]/best",
+                "--no-playlist",
+            ])
+            .kill_on_drop(true)
+            .output()
+            .await?;
+        if !output.status.success() {
+            anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+
+        // No output means that nothing has been found.
+        Ok(output
+            .stdout
+            .split(|byte| *byte == b'\n')
+            .filter_map(|line| serde_json::from_slice::<SearchResult>(line).ok())
+            .find_map(|search_result| Track::try_from(search_result).ok()))
+    }
+}
+
+/// Picks tracks related to the given ones, used to continue the playback once the queue ends.
+pub(crate) struct Recommender {
+    rusty_pipe_client: RustyPipe,
+}
+
+impl Recommender {
+    pub(crate) fn new(rusty_pipe_client: RustyPipe) -> Self {
+        Self { rusty_pipe_client }
+    }
+
+    /// Returns the first of the videos recommended alongside the given track which is not among
+    /// the excluded ones. Only tracks from YouTube can be used as the seed.
+    pub(crate) async fn recommend(
+        &self,
+        track: &Track,
+        excluded_urls: &[&str],
+    ) -> Result<Option<Track>, anyhow::Error> {
+        let url = match &track.source {
+            Source::YouTube { url } => url,
+            _ => return Ok(None),
+        };
+        let query = self.rusty_pipe_client.query();
+
+        let id = match query
+            .resolve_url(url, RESOLVE_YOUTUBE_MUSIC_ALBUM_IDS)
+            .await?
+        {
+            UrlTarget::Video { id, .. } => id,
+            _ => return Ok(None),
+        };
+
+        Ok(query
+            .video_details(id)
+            .await?
+            .recommended
+            .items
+            .into_iter()
+            .map(Track::from)
+            .find(|track| track.url().is_none_or(|url| !excluded_urls.contains(&url))))
     }
 }
 
-impl TryFrom<AuxMetadata> for Track {
+impl TryFrom<SearchResult> for Track {
     type Error = ();
 
-    fn try_from(aux_metadata: AuxMetadata) -> Result<Self, Self::Error> {
-        Ok(Self::new(
-            aux_metadata.title.ok_or(())?,
-            aux_metadata.source_url.ok_or(())?,
-            aux_metadata.thumbnail,
-        ))
+    fn try_from(search_result: SearchResult) -> Result<Self, Self::Error> {
+        let mut track = Self::new(
+            search_result.title.ok_or(())?,
+            Source::YouTube {
+                url: search_result.webpage_url.ok_or(())?,
+            },
+            search_result.thumbnail,
+            search_result
+                .duration
+                .filter(|duration| duration.is_finite() && *duration >= 0.0)
+                .map(Duration::from_secs_f64),
+        );
+        track.artist = search_result.artist.or(search_result.channel);
+        track.album = search_result.album;
+        Ok(track)
     }
 }